@@ -0,0 +1,7 @@
+use std::env;
+
+fn main() {
+    // Used by self-update to find the release asset of the platform
+    let target = env::var("TARGET").unwrap_or_default();
+    println!("cargo:rustc-env=TARGET={target}");
+}
@@ -0,0 +1,227 @@
+## Fehler
+
+error-api = Beim Verbindungsaufbau zur API ist im HTTP-Client ein unerwarteter Fehler aufgetreten
+error-api-token = Der API-Token wurde von Cloudflare abgelehnt (Status: { $status }), api_token in der Konfiguration sollte überprüft werden
+error-config = Beim Einlesen der Konfiguration ist ein Fehler aufgetreten. Eine Beispielkonfiguration befindet sich in der Readme.
+error-control = Beim Einrichten des Steuerungssockets ist ein Fehler aufgetreten.
+error-config-watch = Beim Überwachen der Konfigurationsdatei auf Änderungen ist ein Fehler aufgetreten.
+error-config-path = Beim Ermitteln des Pfads der Konfigurationsdatei ist ein Fehler aufgetreten.
+error-dyndns-credentials = Der DynDNS-Server benötigt einen Benutzernamen und ein Passwort im Abschnitt dyndns der Konfiguration.
+error-events = Beim Überwachen der Adressänderungen der Netzwerkschnittstellen ist ein Fehler aufgetreten.
+error-fixtures = Beim Vorbereiten des Verzeichnisses der aufgezeichneten HTTP-Anfragen ist ein Fehler aufgetreten.
+error-ipv4 = Beim Ermitteln der IPv4-Adresse ist ein Fehler aufgetreten
+error-ipv6 = Beim Ermitteln der IPv6-Adresse ist ein Fehler aufgetreten
+error-json = Beim (De-)Serialisieren von JSON ist ein Fehler aufgetreten
+error-lock = Beim Sperren des Zustandsverzeichnisses ist ein Fehler aufgetreten.
+error-log = Beim Einrichten der Protokollausgabe ist ein Fehler aufgetreten.
+error-no-ip = Weder die IPv4- noch die IPv6-Adresse konnte ermittelt werden
+error-non-address-record = Es wurde ein Eintrag gefunden, der nicht vom Typ "A" oder "AAAA" ist
+error-no-success-http = Eine HTTP-Antwort war nicht erfolgreich (Statuscode nicht zwischen 200-299)
+error-no-success-json = Eine JSON-Antwort enthielt ungültige Daten ({"{"} "success": true {"}"} fehlt)
+error-provided-address = Die angegebene Adresse { $ip } ist nicht global routbar
+error-report = Beim Schreiben der Berichtsdatei ist ein Fehler aufgetreten.
+error-self-update = Beim Aktualisieren des Programms ist ein Fehler aufgetreten.
+error-server = Beim Beantworten von HTTP-Anfragen ist ein Fehler aufgetreten.
+error-service = Beim Installieren des Dienstes ist ein Fehler aufgetreten.
+error-signal = Beim Einrichten der Signalbehandlung ist ein Fehler aufgetreten.
+error-state = Beim Zugriff auf die Zustandsdatei ist ein Fehler aufgetreten.
+error-summary = Beim Schreiben der Job-Zusammenfassung ist ein Fehler aufgetreten.
+error-terminal = Beim Steuern des Terminals ist ein Fehler aufgetreten.
+error-timestamp-format = Ungültiges Zeitstempelformat "{ $format }"
+error-unknown = Ein unerwarteter Fehler ist aufgetreten!
+crash-bundle = Ein Diagnosepaket wurde nach "{ $path }" geschrieben, bitte nach Durchsicht an den Fehlerbericht anhängen
+crash-bundle-failed = Das Diagnosepaket konnte nicht geschrieben werden
+warning-http3-unsupported = HTTP/3 wird von diesem Build nicht unterstützt (Feature http3 aktivieren), stattdessen wird HTTP/2 verwendet
+api-retry = Die API-Anfrage ist fehlgeschlagen ({ $reason }), Versuch { $attempt } von { $attempts } in { $seconds } Sekunden
+api-throttled = Das Budget an API-Anfragen (rate_limit) ist aufgebraucht, es wird { $seconds } Sekunden gewartet
+
+## Einträge
+
+record-address = "{ $record_type }"-Eintrag "{ $name }" in Zone "{ $zone }" mit IP-Adresse "{ $address }"
+record-content = "{ $record_type }"-Eintrag "{ $name }" in Zone "{ $zone }" mit Inhalt "{ $content }"
+addresses-unchanged = Die Adressen haben sich seit der letzten Prüfung der Einträge um { $checked_at } nicht geändert, die Prüfung wird übersprungen
+prefix-changed = Das delegierte Präfix hat sich von { $previous } auf { $current } geändert, alle davon abgeleiteten Einträge werden aktualisiert
+zone-not-found = "{ $zone }" wird übersprungen, da die zugehörige Zone nicht gefunden wurde
+record-tunnel = "{ $name }" wird übersprungen, da er statt auf eine Adresse auf den Cloudflare Tunnel "{ $content }" verweist
+records-not-found = "{ $name }" wird übersprungen, da die zugehörigen Einträge nicht gefunden wurden
+external-change = Von jemand anderem geändert, seit "{ $content }" am { $published_at } veröffentlicht wurde: { $record }
+external-change-skipped = "{ $name }" wird übersprungen, da der Eintrag von jemand anderem geändert wurde (mit --force überschreiben)
+up-to-date = Bereits aktuell: { $record }
+updated = Erfolgreich aktualisiert: { $record }
+created = Erfolgreich erstellt: { $record }
+duplicate-deleted = Duplikat erfolgreich gelöscht: { $record }
+ownership-skipped = Eintrag ohne Eigentümer-Markierung übersprungen: { $record }
+ownership-refused = Eintrag ohne Eigentümer-Markierung wird nicht gelöscht: { $record }
+member-created = Erfolgreich erstellt: { $record }
+member-deleted = Erfolgreich gelöscht: { $record }
+update-limit = AKTUALISIERUNGSLIMIT ERREICHT: Innerhalb der letzten Stunde wurden { $limit } Aktualisierungen vorgenommen, möglicherweise wegen einer fehlerhaften Erkennungsquelle. Wird unterdrückt (mit --force erzwingen): { $record }
+custom-hostname-not-found = Der benutzerdefinierte Hostname "{ $hostname }" existiert nicht in der Zone "{ $zone }"
+custom-origin-up-to-date = Der benutzerdefinierte Ursprungsserver von "{ $hostname }" ist aktuell: "{ $origin }"
+custom-origin-updated = Der benutzerdefinierte Ursprungsserver von "{ $hostname }" wurde auf "{ $origin }" geändert
+fallback-origin-up-to-date = Der Fallback-Ursprung der Zone "{ $zone }" ist aktuell: "{ $origin }"
+fallback-origin-updated = Der Fallback-Ursprung der Zone "{ $zone }" wurde auf "{ $origin }" geändert
+spectrum-up-to-date = Der Ursprung der Spectrum-Anwendung "{ $app }" ist aktuell: "{ $origin }"
+spectrum-updated = Der Ursprung der Spectrum-Anwendung "{ $app }" wurde auf "{ $origin }" geändert
+failover-active = Der Ersatz von "{ $name }" wird nach { $count } Prüfungen ohne ermittelte Adresse veröffentlicht
+failover-recovered = Die Ermittlung von "{ $name }" funktioniert wieder, die ermittelten Adressen werden wieder veröffentlicht
+uplink-no-ip = Weder die IPv4- noch die IPv6-Adresse des Uplinks "{ $uplink }" konnte ermittelt werden
+uplink-unknown = "{ $name }" wird übersprungen, da der Uplink "{ $uplink }" nicht konfiguriert ist
+cooldown-deferred = Bis { $until } zurückgestellt, da der Eintrag kürzlich aktualisiert wurde (mit --force erzwingen): { $record }
+maintenance-queued = Bis zum nächsten Wartungsfenster vorgemerkt: { $record }
+maintenance-invalid = Ungültiges Wartungsfenster "{ $window }": { $error }
+verified = Überprüft: "{ $name }" wird zu "{ $address }" aufgelöst
+verification-failed = Überprüfung fehlgeschlagen: "{ $name }" wurde zu { $result } statt zu "{ $address }" aufgelöst
+verification-no-address = keiner Adresse
+verification-error = einem Fehler ({ $error })
+dns-invalid-name = Ungültiger Domainname "{ $name }"
+dns-unexpected-response = Unerwartete DNS-Antwort
+dns-truncated-response = Abgeschnittene DNS-Antwort
+dns-error-code = Der DNS-Resolver hat mit dem Fehlercode { $code } geantwortet
+dns-malformed-response = Fehlerhafte DNS-Antwort
+cache-stale-zone = Die zwischengespeicherte ID der Zone "{ $zone }" ist nicht mehr gültig, die Zonen werden erneut abgefragt
+batch-fallback = Die Sammelanfrage für die Zone "{ $zone }" ist fehlgeschlagen, die Einträge werden einzeln aktualisiert
+patch-rejected = Die PATCH-Anfrage für "{ $name }" wurde abgelehnt ({ $status }), der Eintrag wird stattdessen mit PUT überschrieben
+
+## Aktionen
+
+template-failed = Beim Rendern der Vorlage "{ $template }" ist ein Fehler aufgetreten.
+template-skipped = Vorlage "{ $template }" wird übersprungen, da die Adresse für { $placeholder } unbekannt ist
+template-rendered = Vorlage "{ $template }" erfolgreich nach "{ $destination }" gerendert
+workers-kv-failed = Beim Schreiben des Workers-KV-Schlüssels "{ $key }" ist ein Fehler aufgetreten.
+failover-command-failed = Beim Ausführen des Failover-Befehls für "{ $name }" ist ein Fehler aufgetreten.
+workers-kv-written = Die ermittelten Adressen wurden erfolgreich in den Workers-KV-Schlüssel "{ $key }" geschrieben
+
+## Selbstaktualisierung
+
+self-update-latest = Die neueste Version { $version } ist bereits installiert
+self-update-available = Version { $version } ist verfügbar (installiert ist { $current })
+self-update-done = Erfolgreich von { $current } auf { $version } aktualisiert
+self-update-no-asset = Die Version { $version } enthält "{ $asset }" für diese Plattform nicht
+self-update-no-checksum = Die Version { $version } enthält die Prüfsumme "{ $asset }" nicht
+self-update-checksum-mismatch = Die Prüfsumme der heruntergeladenen Datei stimmt nicht überein (erwartet { $expected }, erhalten { $actual })
+
+## Daemon
+
+systemd-status = Letzte Prüfung: IPv4 { $ipv4 }, IPv6 { $ipv6 }
+daemon-schedule-invalid = Ungültiger Zeitplan "{ $schedule }", stattdessen wird das Intervall verwendet: { $error }
+daemon-reloaded = Konfiguration neu geladen
+daemon-reload-failed = Die bisherige Konfiguration wird beibehalten, da die neue ungültig ist
+daemon-record-added = Der Eintrag "{ $name }" wurde hinzugefügt
+daemon-record-removed = Der Eintrag "{ $name }" wurde entfernt
+control-listening = Steuerungsbefehle werden auf "{ $path }" entgegengenommen
+control-denied = Steuerungsverbindung von Benutzer { $uid } abgelehnt
+signal-sync = SIGUSR1 empfangen, es wird sofort geprüft
+config-changed = Die Konfigurationsdatei wurde geändert, sie wird neu eingelesen
+signal-reload = SIGHUP empfangen, die Konfiguration wird neu geladen
+event-address-added = Die Adresse { $address } wurde hinzugefügt, es wird sofort geprüft
+event-address-removed = Die Adresse { $address } wurde entfernt, es wird sofort geprüft
+event-address-changed = Die Adressen einer Netzwerkschnittstelle haben sich geändert, es wird sofort geprüft
+event-interface-not-found = Die Netzwerkschnittstelle "{ $interface }" existiert nicht
+event-interfaces-unsupported = Das Einschränken der Ereignisse bei Adressänderungen auf bestimmte Netzwerkschnittstellen wird auf diesem System nicht unterstützt, alle Schnittstellen werden berücksichtigt
+event-overflow = Adressänderungen sind verloren gegangen, weil zu viele auf einmal eingetroffen sind, es wird sofort geprüft
+event-listener-failed = Das Warten auf Adressänderungen ist fehlgeschlagen ({ $error }), Änderungen werden nur noch bei den regelmäßigen Prüfungen bemerkt
+events-unsupported = Ereignisse bei Adressänderungen werden auf diesem System nicht unterstützt
+control-unsupported = Der Steuerungssocket wird nur auf Unix-artigen Systemen unterstützt
+
+## Dienst
+
+service-written = "{ $path }" geschrieben
+service-enable = Den Dienst aktivieren mit: { $command }
+service-registered = Die geplante Aufgabe "{ $name }" wurde registriert
+
+## Start
+
+startup-delay = Vor der ersten Prüfung wird { $seconds } Sekunden gewartet
+startup-waiting = Es konnte keine Adresse ermittelt werden, es wird bis zu { $seconds } Sekunden erneut versucht
+startup-online = Eine Adresse wurde ermittelt, es wird jetzt geprüft
+startup-offline = Auch nach { $seconds } Sekunden konnte keine Adresse ermittelt werden, es wird trotzdem geprüft
+
+## Beenden
+
+shutdown-requested = Beenden nach dem aktuellen Eintrag, erneut Strg+C drücken, um sofort zu beenden
+shutdown-forced = Sofortiges Beenden, der Zustand des aktuellen Laufs wird nicht gespeichert
+
+## Aufzeichnung
+
+fixture-failed = Beim Aufzeichnen der HTTP-Anfrage nach "{ $path }" ist ein Fehler aufgetreten.
+fixture-missing = Keine aufgezeichnete Antwort für { $method } { $url } (erwartet "{ $path }")
+
+## Hochverfügbarkeit
+
+ha-no-node = Die Kennung dieses Hosts konnte nicht ermittelt werden, bitte high_availability.node konfigurieren
+ha-standby = Bereitschaft, da "{ $leader }" der Leader ist (letzter Herzschlag vor { $age } Sekunden)
+ha-takeover = Übernahme von "{ $leader }", da dessen letzter Herzschlag { $age } Sekunden zurückliegt
+
+## Sperre
+
+lock-busy = Eine andere Instanz aktualisiert bereits die Einträge, diese Prüfung wird übersprungen
+lock-waiting = Es wird gewartet, bis eine andere Instanz die Einträge aktualisiert hat
+
+## Echo-Server
+
+echo-serving = IP-Echo wird auf { $address } bereitgestellt
+
+## DynDNS-Server
+
+dyndns-serving = DynDNS-Aktualisierungen werden auf { $address } angenommen
+dyndns-update = Aktualisierung von { $hostnames } auf { $addresses } angefordert von { $peer }
+
+## Bereinigung
+
+prune-nothing = Es sind keine veröffentlichten Einträge übrig, nichts zu löschen
+prune-candidate = Nicht mehr konfiguriert: { $record }
+prune-confirm = { $count } Einträge löschen? [j/N]
+prune-unconfirmed = Ohne Terminal zur Bestätigung werden die Einträge nicht gelöscht, mit --yes werden sie trotzdem gelöscht
+prune-aborted = Es wurden keine Einträge gelöscht
+prune-deleted = Erfolgreich gelöscht: { $record }
+prune-missing = Bereits von jemand anderem gelöscht: { $record }
+
+## Erkennung
+
+cgnat-detected = Die IPv4-Adresse { $ip } gehört zu einem Carrier-Grade-NAT (100.64.0.0/10) und ist aus dem Internet nicht erreichbar
+cgnat-skipped = Die IPv4-Adresse wird übersprungen, da detection.skip_on_cgnat aktiviert ist
+detection-private = Die Adresse { $ip } von "{ $source }" wird ignoriert, da sie nicht global routbar ist
+detection-timeout = "{ $source }" hat nicht innerhalb von { $seconds } Sekunden geantwortet
+detection-budget = Die Ermittlung einer Adressfamilie hat länger als { $seconds } Sekunden gedauert
+detection-fallback = Die Adresse { $ip } wurde mit der Ausweichquelle "{ $source }" ermittelt
+detection-no-consensus = Weniger als { $required } Quellen haben dieselbe Adresse zurückgegeben: { $answers }
+geoip-database = Die GeoIP-Datenbank { $path } konnte nicht gelesen werden: { $error }
+geoip-network = Die Adresse { $ip } gehört zu { $network } in { $country }
+geoip-rejected = Die Adresse { $ip } wird ignoriert, da detection.geoip.reject aktiviert ist
+geoip-unexpected = Die Adresse { $ip } gehört zum unerwarteten Netz { $network } in { $country }, das deutet meist auf ein VPN-Leck oder eine kompromittierte Erkennungsquelle hin
+geoip-unknown = unbekannt
+
+## Status eines Eintrags
+
+action-created = erstellt
+action-deferred = zurückgestellt
+action-deleted = gelöscht
+action-failed = fehlgeschlagen
+action-not-found = nicht gefunden
+action-queued = vorgemerkt
+action-skipped = übersprungen
+action-tunnel = Tunnel
+action-up-to-date = aktuell
+action-updated = aktualisiert
+status-changed-externally = { $action } (extern geändert)
+
+## Tabellen
+
+column-zone = Zone
+column-name = Name
+column-type = Typ
+column-content = Inhalt
+column-current = Aktuell
+column-desired = Gewünscht
+column-status = Status
+column-last-update = Letzte Änderung
+column-target = Ziel
+column-success = Erfolg
+
+## Watch
+
+watch-records = Einträge
+watch-records-checked = Einträge (zuletzt geprüft { $time })
+watch-log = Protokoll
+watch-checking = Einträge werden geprüft...
+watch-next-check = Nächste Prüfung in { $seconds }s ("r" für sofortige Prüfung, "q" zum Beenden)
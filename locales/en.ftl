@@ -0,0 +1,227 @@
+## Errors
+
+error-api = The HTTP client encountered an unexpected error while trying to connect to the API
+error-api-token = The API token was rejected by Cloudflare (status: { $status }), check api_token in the configuration
+error-config = An error occurred while parsing the configuration. Please consult the readme for an example configuration.
+error-control = An error occurred while setting up the control socket.
+error-config-watch = An error occurred while watching the configuration file for changes.
+error-config-path = An error occurred while trying to get the path to the configuration file.
+error-dyndns-credentials = The DynDNS server requires a username and password in the dyndns section of the configuration.
+error-events = An error occurred while listening for address changes of the network interfaces.
+error-fixtures = An error occurred while preparing the directory of the recorded HTTP requests.
+error-ipv4 = An error occurred while trying to determine the IPv4 address
+error-ipv6 = An error occurred while trying to determine the IPv6 address
+error-json = An error occurred while (de)serializing JSON
+error-lock = An error occurred while locking the state directory.
+error-log = An error occurred while setting up the log output.
+error-no-ip = Neither IPv4 nor IPv6 address could be determined
+error-non-address-record = Encountered a record that was not of type "A" or "AAAA"
+error-no-success-http = A HTTP response was unsuccessful (status code not between 200-299)
+error-no-success-json = A JSON response contained invalid data (missing {"{"} "success": true {"}"})
+error-provided-address = The provided address { $ip } is not globally routable
+error-report = An error occurred while writing the report file.
+error-self-update = An error occurred while updating the program.
+error-server = An error occurred while serving HTTP requests.
+error-service = An error occurred while installing the service.
+error-signal = An error occurred while setting up the signal handlers.
+error-state = An error occurred while accessing the state file.
+error-summary = An error occurred while writing the job summary.
+error-terminal = An error occurred while controlling the terminal.
+error-timestamp-format = Invalid timestamp format "{ $format }"
+error-unknown = An unexpected error occured!
+crash-bundle = A diagnostic bundle has been written to "{ $path }", please attach it to the bug report after reviewing it
+crash-bundle-failed = The diagnostic bundle could not be written
+warning-http3-unsupported = HTTP/3 is not supported by this build (enable the http3 feature), falling back to HTTP/2
+api-retry = The API request failed ({ $reason }), attempt { $attempt } of { $attempts } in { $seconds } seconds
+api-throttled = The budget of API requests (rate_limit) is used up, waiting { $seconds } seconds
+
+## Records
+
+record-address = "{ $record_type }" Record "{ $name }" in zone "{ $zone }" with IP address "{ $address }"
+record-content = "{ $record_type }" Record "{ $name }" in zone "{ $zone }" with content "{ $content }"
+addresses-unchanged = The addresses have not changed since the records were last checked at { $checked_at }, skipping the check
+prefix-changed = The delegated prefix has changed from { $previous } to { $current }, refreshing all records derived from it
+zone-not-found = Skipping "{ $zone }" because the corresponding zone could not be found
+record-tunnel = Skipping "{ $name }" because it points at the Cloudflare Tunnel "{ $content }" instead of an address
+records-not-found = Skipping "{ $name }" because the corresponding records could not be found
+external-change = Changed by someone else since "{ $content }" was published at { $published_at }: { $record }
+external-change-skipped = Skipping "{ $name }" because it has been changed by someone else (use --force to overwrite)
+up-to-date = Already up-to-date: { $record }
+updated = Successfully updated: { $record }
+created = Successfully created: { $record }
+duplicate-deleted = Successfully deleted duplicate: { $record }
+ownership-skipped = Skipped record without the ownership marker: { $record }
+ownership-refused = Not deleting record without the ownership marker: { $record }
+member-created = Successfully created: { $record }
+member-deleted = Successfully deleted: { $record }
+update-limit = UPDATE LIMIT REACHED: { $limit } updates have been made within the last hour, which may be caused by a broken detection source. Suppressing (use --force to override): { $record }
+custom-hostname-not-found = The custom hostname "{ $hostname }" does not exist in the zone "{ $zone }"
+custom-origin-up-to-date = The custom origin server of "{ $hostname }" is up to date: "{ $origin }"
+custom-origin-updated = Updated the custom origin server of "{ $hostname }" to "{ $origin }"
+fallback-origin-up-to-date = The fallback origin of the zone "{ $zone }" is up to date: "{ $origin }"
+fallback-origin-updated = Updated the fallback origin of the zone "{ $zone }" to "{ $origin }"
+spectrum-up-to-date = The origin of the Spectrum application "{ $app }" is up to date: "{ $origin }"
+spectrum-updated = Updated the origin of the Spectrum application "{ $app }" to "{ $origin }"
+failover-active = Publishing the fallback of "{ $name }" after { $count } checks without a detected address
+failover-recovered = The detection of "{ $name }" recovered, publishing the detected addresses again
+uplink-no-ip = Neither IPv4 nor IPv6 address of uplink "{ $uplink }" could be determined
+uplink-unknown = Skipping "{ $name }" because the uplink "{ $uplink }" is not configured
+cooldown-deferred = Deferred until { $until } because the record has been updated recently (use --force to override): { $record }
+maintenance-queued = Queued until the next maintenance window: { $record }
+maintenance-invalid = Invalid maintenance window "{ $window }": { $error }
+verified = Verified: "{ $name }" resolves to "{ $address }"
+verification-failed = Verification failed: "{ $name }" resolved to { $result } instead of "{ $address }"
+verification-no-address = no address
+verification-error = an error ({ $error })
+dns-invalid-name = Invalid domain name "{ $name }"
+dns-unexpected-response = Unexpected DNS response
+dns-truncated-response = Truncated DNS response
+dns-error-code = DNS resolver responded with error code { $code }
+dns-malformed-response = Malformed DNS response
+cache-stale-zone = The cached ID of the zone "{ $zone }" is no longer valid, the zones are listed again
+batch-fallback = The batch request for the zone "{ $zone }" failed, the records are updated one by one
+patch-rejected = The PATCH request for "{ $name }" was rejected ({ $status }), the record is overwritten with PUT instead
+
+## Actions
+
+template-failed = An error occurred while rendering the template "{ $template }".
+template-skipped = Skipping template "{ $template }" because the address for { $placeholder } is unknown
+template-rendered = Successfully rendered template "{ $template }" to "{ $destination }"
+workers-kv-failed = An error occurred while writing the Workers KV key "{ $key }".
+failover-command-failed = An error occurred while running the failover command for "{ $name }".
+workers-kv-written = Successfully wrote the detected addresses to the Workers KV key "{ $key }"
+
+## Self-update
+
+self-update-latest = Already running the latest release { $version }
+self-update-available = Release { $version } is available (running { $current })
+self-update-done = Successfully updated from { $current } to { $version }
+self-update-no-asset = The release { $version } does not contain "{ $asset }" for this platform
+self-update-no-checksum = The release { $version } does not contain the checksum "{ $asset }"
+self-update-checksum-mismatch = The checksum of the downloaded file does not match (expected { $expected }, got { $actual })
+
+## Daemon
+
+systemd-status = Last check: IPv4 { $ipv4 }, IPv6 { $ipv6 }
+daemon-schedule-invalid = Invalid schedule "{ $schedule }", using the interval instead: { $error }
+daemon-reloaded = Reloaded the configuration
+daemon-reload-failed = Keeping the previous configuration because the new one is invalid
+daemon-record-added = Added the record "{ $name }"
+daemon-record-removed = Removed the record "{ $name }"
+control-listening = Listening for control commands on "{ $path }"
+control-denied = Rejected control connection of user { $uid }
+signal-sync = Received SIGUSR1, checking now
+config-changed = The configuration file has changed, reloading it
+signal-reload = Received SIGHUP, reloading the configuration
+event-address-added = The address { $address } has been added, checking now
+event-address-removed = The address { $address } has been removed, checking now
+event-address-changed = The addresses of a network interface have changed, checking now
+event-interface-not-found = The network interface "{ $interface }" does not exist
+event-interfaces-unsupported = Restricting the address change events to certain network interfaces is not supported on this system, all interfaces are considered
+event-overflow = Address changes were lost because too many arrived at once, checking now
+event-listener-failed = Listening for address changes failed ({ $error }), changes are only noticed by the regular checks
+events-unsupported = Address change events are not supported on this system
+control-unsupported = The control socket is only supported on Unix-like systems
+
+## Service
+
+service-written = Written "{ $path }"
+service-enable = Enable the service with: { $command }
+service-registered = Registered the scheduled task "{ $name }"
+
+## Startup
+
+startup-delay = Waiting { $seconds } seconds before the first check
+startup-waiting = No address could be determined, retrying for up to { $seconds } seconds
+startup-online = An address has been determined, checking now
+startup-offline = Still no address could be determined after { $seconds } seconds, checking anyway
+
+## Shutdown
+
+shutdown-requested = Stopping after the current record, press Ctrl-C again to exit immediately
+shutdown-forced = Exiting immediately, the state of the current run is not saved
+
+## Recording
+
+fixture-failed = An error occurred while recording the HTTP request to "{ $path }".
+fixture-missing = No recorded response for { $method } { $url } (expected "{ $path }")
+
+## High availability
+
+ha-no-node = The identifier of this host could not be determined, please configure high_availability.node
+ha-standby = Standing by because "{ $leader }" is the leader (last heartbeat { $age } seconds ago)
+ha-takeover = Taking over from "{ $leader }" because its last heartbeat was { $age } seconds ago
+
+## Lock
+
+lock-busy = Another instance is already updating the records, skipping this check
+lock-waiting = Waiting for another instance to finish updating the records
+
+## Echo server
+
+echo-serving = Serving IP echo on { $address }
+
+## DynDNS server
+
+dyndns-serving = Accepting DynDNS updates on { $address }
+dyndns-update = Update of { $hostnames } to { $addresses } requested by { $peer }
+
+## Prune
+
+prune-nothing = No published records are left over, nothing to delete
+prune-candidate = No longer configured: { $record }
+prune-confirm = Delete { $count } records? [y/N]
+prune-unconfirmed = The records are not deleted without a terminal to confirm, use --yes to delete them anyway
+prune-aborted = No records have been deleted
+prune-deleted = Successfully deleted: { $record }
+prune-missing = Already deleted by someone else: { $record }
+
+## Detection
+
+cgnat-detected = The IPv4 address { $ip } belongs to a carrier-grade NAT (100.64.0.0/10) and is not reachable from the internet
+cgnat-skipped = Skipping the IPv4 address because detection.skip_on_cgnat is enabled
+detection-private = Ignoring the address { $ip } of "{ $source }" because it is not globally routable
+detection-timeout = "{ $source }" did not answer within { $seconds } seconds
+detection-budget = The detection of an address family took longer than { $seconds } seconds
+detection-fallback = Determined the address { $ip } with the fallback "{ $source }"
+detection-no-consensus = Fewer than { $required } sources returned the same address: { $answers }
+geoip-database = Could not read the GeoIP database { $path }: { $error }
+geoip-network = The address { $ip } belongs to { $network } in { $country }
+geoip-rejected = Ignoring the address { $ip } because detection.geoip.reject is enabled
+geoip-unexpected = The address { $ip } belongs to the unexpected network { $network } in { $country }, this usually indicates a VPN leak or a compromised detection source
+geoip-unknown = unknown
+
+## Status of a record
+
+action-created = created
+action-deferred = deferred
+action-deleted = deleted
+action-failed = failed
+action-not-found = not found
+action-queued = queued
+action-skipped = skipped
+action-tunnel = tunnel
+action-up-to-date = up-to-date
+action-updated = updated
+status-changed-externally = { $action } (changed externally)
+
+## Tables
+
+column-zone = Zone
+column-name = Name
+column-type = Type
+column-content = Content
+column-current = Current
+column-desired = Desired
+column-status = Status
+column-last-update = Last update
+column-target = Target
+column-success = Success
+
+## Watch
+
+watch-records = Records
+watch-records-checked = Records (last check { $time })
+watch-log = Log
+watch-checking = Checking records...
+watch-next-check = Next check in { $seconds }s (press "r" to check now, "q" to quit)
@@ -0,0 +1,177 @@
+use crate::{
+    api::{api_get, api_put, client, deserialize_response, Auth},
+    errors::{handle_errors, message, ErrorKind},
+    i18n::t,
+    output,
+    structs::{
+        cloudflare::request::PutWorkersKvValue,
+        config::{Config, TemplateAction, WorkersKvAction},
+        report::Report,
+    },
+};
+use chrono::Utc;
+use reqwest::{Client as HttpClient, Url};
+use std::{fs, io::Error as IOError, path::PathBuf};
+use tokio::process::Command;
+
+pub(crate) async fn run(config: &Config, report: &Report) {
+    for action in &config.actions.templates {
+        if let Err(e) = render_template(action, report).await {
+            output::error(format!(
+                "{}\n{e}",
+                t!("template-failed", template = action.template.display())
+            ));
+        }
+    }
+
+    if config.actions.workers_kv.is_empty() {
+        return;
+    }
+
+    let http = match client(config) {
+        Ok(x) => x,
+        Err(e) => {
+            handle_errors(&ErrorKind::Unknown(Box::new(e)));
+            return;
+        }
+    };
+
+    for action in &config.actions.workers_kv {
+        if let Err(e) = write_workers_kv(&http, config, action, report).await {
+            output::error(format!(
+                "{}\n{e}",
+                t!("workers-kv-failed", key = action.key)
+            ));
+        }
+    }
+}
+
+async fn write_workers_kv(
+    http: &HttpClient,
+    config: &Config,
+    action: &WorkersKvAction,
+    report: &Report,
+) -> Result<(), IOError> {
+    if report.ipv4.is_none() && report.ipv6.is_none() {
+        return Ok(());
+    }
+
+    let url = Url::parse(&format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
+        action.account_id, action.namespace_id, action.key
+    ))
+    .map_err(IOError::other)?;
+
+    // Reading a key returns its raw value instead of the usual API response
+    let current = match api_get(http, url.to_owned(), Auth::of(config)).await {
+        Ok(x) if x.status().is_success() => x.json::<PutWorkersKvValue>().await.ok(),
+        Ok(_) => None,
+        Err(e) => return Err(IOError::other(e)),
+    };
+    if current.is_some_and(|x| x.ipv4 == report.ipv4 && x.ipv6 == report.ipv6) {
+        return Ok(());
+    }
+
+    let value = PutWorkersKvValue {
+        ipv4: report.ipv4,
+        ipv6: report.ipv6,
+        updated_at: Utc::now(),
+    };
+    let response = api_put(http, url, Auth::of(config), &value)
+        .await
+        .map_err(IOError::other)?;
+    deserialize_response(response)
+        .await
+        .map_err(|e| IOError::other(message(&e)))?;
+
+    output::info(t!("workers-kv-written", key = action.key));
+    Ok(())
+}
+
+async fn render_template(action: &TemplateAction, report: &Report) -> Result<(), IOError> {
+    let template = fs::read_to_string(&action.template)?;
+
+    let placeholders = [
+        ("{ipv4}", report.ipv4.map(|x| x.to_string())),
+        ("{ipv6}", report.ipv6.map(|x| x.to_string())),
+    ];
+
+    let mut rendered = template.to_owned();
+    for (placeholder, value) in placeholders {
+        if !template.contains(placeholder) {
+            continue;
+        }
+        match value {
+            Some(x) => rendered = rendered.replace(placeholder, &x),
+            None => {
+                output::info(t!(
+                    "template-skipped",
+                    template = action.template.display(),
+                    placeholder = placeholder
+                ));
+                return Ok(());
+            }
+        }
+    }
+
+    if fs::read_to_string(&action.destination).is_ok_and(|x| x == rendered) {
+        return Ok(());
+    }
+
+    let mut temporary = PathBuf::from(&action.destination);
+    temporary.as_mut_os_string().push(".new");
+    fs::write(&temporary, rendered)?;
+    fs::rename(&temporary, &action.destination)?;
+
+    output::info(t!(
+        "template-rendered",
+        template = action.template.display(),
+        destination = action.destination.display()
+    ));
+
+    if let Some(command) = &action.command {
+        run_command(command, &[]).await?;
+    }
+
+    Ok(())
+}
+
+/// Notifies about a record switching to its failover fallback (active) or back to the detection
+pub(crate) async fn notify_failover(config: &Config, name: &str, is_active: bool) {
+    let command = match &config.actions.failover {
+        Some(x) => x,
+        None => return,
+    };
+    let transition = match is_active {
+        true => "active",
+        false => "recovered",
+    };
+
+    let environment = [("DDNS_RECORD", name), ("DDNS_FAILOVER", transition)];
+    if let Err(e) = run_command(command, &environment).await {
+        output::error(format!(
+            "{}\n{e}",
+            t!("failover-command-failed", name = name)
+        ));
+    }
+}
+
+async fn run_command(command: &str, environment: &[(&str, &str)]) -> Result<(), IOError> {
+    let (shell, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    let status = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .envs(environment.iter().copied())
+        .status()
+        .await?;
+
+    match status.success() {
+        true => Ok(()),
+        false => Err(IOError::other(format!(
+            "The command \"{command}\" exited with {status}"
+        ))),
+    }
+}
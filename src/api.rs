@@ -1,32 +1,439 @@
-use reqwest::{header, Client as HttpClient, Response, Url};
-use serde::Serialize;
+use crate::{
+    errors::ErrorKind,
+    fixtures,
+    i18n::t,
+    output, shutdown,
+    structs::{
+        cloudflare::{response::VerifyToken, ApiMessage, Cloudflare},
+        config::{Config, Retry},
+    },
+};
+use chrono::{DateTime, Utc};
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    Client as HttpClient, ClientBuilder, Method, RequestBuilder, Response, Url,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    hash::{BuildHasher, Hasher, RandomState},
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::time::{sleep, Instant};
+
+/// Largest page size that the zones endpoint accepts
+pub(crate) const ZONES_PER_PAGE: u32 = 50;
+/// Page size of the records of a zone, large zones are listed with few requests
+pub(crate) const RECORDS_PER_PAGE: u32 = 5000;
+
+static RETRY: Mutex<Retry> = Mutex::new(Retry::DEFAULT);
+/// Requests that may be sent within the window of the rate limit (0 = unlimited), Cloudflare allows
+/// 1200 requests per 5 minutes and API token
+static BUDGET: Mutex<u32> = Mutex::new(1200);
+/// Times of the requests within the window per credentials (see Auth::id), kept across the checks
+/// of the daemon
+static SENT: Mutex<BTreeMap<String, VecDeque<Instant>>> = Mutex::new(BTreeMap::new());
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(300);
+/// Longest Retry-After that is waited for instead of failing the request
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Credentials of the requests to the API
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Auth<'a> {
+    Token(&'a str),
+    /// Legacy Global API Key together with the email address of the user
+    Key {
+        email: &'a str,
+        key: &'a str,
+    },
+}
+
+impl<'a> Auth<'a> {
+    /// The Global API Key is used if it is configured together with the email address
+    pub(crate) fn of(config: &'a Config) -> Self {
+        match (&config.api_email, &config.api_key) {
+            (Some(email), Some(key)) => Self::Key { email, key },
+            _ => Self::Token(&config.api_token),
+        }
+    }
+
+    /// Identifier of the credentials that doesn't reveal them, e.g. to key the cache by account
+    pub(crate) fn id(self) -> String {
+        let credentials = match self {
+            Self::Token(token) => token.to_owned(),
+            Self::Key { email, key } => format!("{email}:{key}"),
+        };
+        Sha256::digest(credentials.as_bytes())
+            .iter()
+            .take(8)
+            .map(|x| format!("{x:02x}"))
+            .collect()
+    }
+
+    fn headers(self) -> HeaderMap {
+        let values = match self {
+            Self::Token(token) => vec![(header::AUTHORIZATION, format!("Bearer {token}"))],
+            Self::Key { email, key } => vec![
+                (HeaderName::from_static("x-auth-email"), email.to_owned()),
+                (HeaderName::from_static("x-auth-key"), key.to_owned()),
+            ],
+        };
+        let mut headers = HeaderMap::new();
+        for (name, value) in values {
+            if let Ok(mut value) = HeaderValue::from_str(&value) {
+                value.set_sensitive(true);
+                headers.insert(name, value);
+            }
+        }
+        headers
+    }
+}
+
+/// Applies the retry policy and rate limit of the configuration to all further API requests
+pub(crate) fn configure(config: &Config) {
+    *RETRY.lock().unwrap_or_else(|e| e.into_inner()) = config.retry;
+    *BUDGET.lock().unwrap_or_else(|e| e.into_inner()) = config.rate_limit;
+}
+
+/// Creates a builder for HTTP clients according to the network settings of the configuration,
+/// HTTP/3 is only used for requests to Cloudflare, which is known to support it
+pub(crate) fn client_builder(config: &Config, is_cloudflare: bool) -> ClientBuilder {
+    let builder = HttpClient::builder();
+
+    #[cfg(feature = "http3")]
+    if config.http3 && is_cloudflare {
+        return builder.http3_prior_knowledge();
+    }
+
+    #[cfg(not(feature = "http3"))]
+    let _ = (config.http3, is_cloudflare);
+
+    builder
+}
+
+pub(crate) fn client(config: &Config) -> Result<HttpClient, reqwest::Error> {
+    client_builder(config, true).build()
+}
 
 pub(crate) async fn api_get(
     http: &HttpClient,
     url: Url,
-    api_token: &str,
+    auth: Auth<'_>,
 ) -> Result<Response, reqwest::Error> {
-    let response = http
+    let request = http
         .get(url)
-        .bearer_auth(api_token)
-        .header(header::ACCEPT, "application/json")
-        .send()
-        .await?;
+        .headers(auth.headers())
+        .header(header::ACCEPT, "application/json");
+    let response = send(request, auth).await?;
+    Ok(response)
+}
+
+/// Requests every page of a list endpoint and combines their results, the outer error is the one of
+/// the HTTP client and the inner one the error of a response
+pub(crate) async fn api_get_all(
+    http: &HttpClient,
+    url: Url,
+    auth: Auth<'_>,
+    per_page: u32,
+) -> Result<Result<Cloudflare, ErrorKind>, reqwest::Error> {
+    let mut results = Vec::new();
+    let mut page = 1;
+    loop {
+        let mut url_page = url.to_owned();
+        url_page
+            .query_pairs_mut()
+            .append_pair("page", &page.to_string())
+            .append_pair("per_page", &per_page.to_string());
+
+        let response = api_get(http, url_page, auth).await?;
+        let data = match deserialize_response(response).await {
+            Ok(x) => x,
+            Err(e) => return Ok(Err(e)),
+        };
+        let items = match data.result {
+            Json::Array(x) => x,
+            _ => return Ok(Err(ErrorKind::Json)),
+        };
+
+        // Without the number of pages a page that is not full is the last one
+        let count = items.len();
+        let is_last = match data.result_info.and_then(|x| x.total_pages) {
+            Some(total_pages) => page >= total_pages,
+            None => count < usize::try_from(per_page).unwrap_or(usize::MAX),
+        };
+        results.extend(items);
+        if is_last || count == 0 {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(Ok(Cloudflare {
+        success: true,
+        errors: Vec::new(),
+        messages: Vec::new(),
+        result: Json::Array(results),
+        result_info: None,
+    }))
+}
+
+pub(crate) async fn api_delete(
+    http: &HttpClient,
+    url: Url,
+    auth: Auth<'_>,
+) -> Result<Response, reqwest::Error> {
+    let request = http
+        .delete(url)
+        .headers(auth.headers())
+        .header(header::ACCEPT, "application/json");
+    let response = send(request, auth).await?;
     Ok(response)
 }
 
 pub(crate) async fn api_patch<T: Serialize>(
     http: &HttpClient,
     url: Url,
-    api_token: &str,
+    auth: Auth<'_>,
     body: T,
 ) -> Result<Response, reqwest::Error> {
-    let response = http
+    let request = http
         .patch(url)
-        .bearer_auth(api_token)
+        .headers(auth.headers())
+        .header(header::ACCEPT, "application/json")
+        .json(&body);
+    let response = send(request, auth).await?;
+    Ok(response)
+}
+
+pub(crate) async fn api_post<T: Serialize>(
+    http: &HttpClient,
+    url: Url,
+    auth: Auth<'_>,
+    body: T,
+) -> Result<Response, reqwest::Error> {
+    let request = http
+        .post(url)
+        .headers(auth.headers())
+        .header(header::ACCEPT, "application/json")
+        .json(&body);
+    let response = send(request, auth).await?;
+    Ok(response)
+}
+
+pub(crate) async fn api_put<T: Serialize>(
+    http: &HttpClient,
+    url: Url,
+    auth: Auth<'_>,
+    body: T,
+) -> Result<Response, reqwest::Error> {
+    let request = http
+        .put(url)
+        .headers(auth.headers())
         .header(header::ACCEPT, "application/json")
-        .json(&body)
-        .send()
-        .await?;
+        .json(&body);
+    let response = send(request, auth).await?;
     Ok(response)
 }
+
+/// Sends a request and repeats it with exponential backoff while it fails transiently (rate limit,
+/// server error or network error)
+async fn send(request: RequestBuilder, auth: Auth<'_>) -> Result<Response, reqwest::Error> {
+    let retry = *RETRY.lock().unwrap_or_else(|e| e.into_inner());
+    // A POST (e.g. creating a record) that may have been applied despite the failure is not repeated,
+    // otherwise the record could be created twice
+    let is_idempotent = request
+        .try_clone()
+        .and_then(|x| x.build().ok())
+        .is_some_and(|x| x.method() != Method::POST);
+    let account = auth.id();
+    let mut attempt = 1;
+    loop {
+        throttle(&account).await;
+        let current = match request.try_clone() {
+            Some(x) if attempt < retry.attempts => x,
+            _ => return fixtures::send(request).await,
+        };
+
+        let result = fixtures::send(current).await;
+        let retry_after = match &result {
+            Ok(x) if x.status() == 429 => retry_after(x),
+            Ok(x)
+                if x.status().is_server_error() && (is_idempotent || retry_after(x).is_some()) =>
+            {
+                retry_after(x)
+            }
+            // The request has not been sent if the connection could not be established
+            Err(e) if e.is_connect() => None,
+            Err(e) if is_idempotent && (e.is_timeout() || e.is_request()) => None,
+            _ => return result,
+        };
+        // Waiting longer than the rate limit window of Cloudflare is not worth it
+        if shutdown::requested() || retry_after.is_some_and(|x| x > MAX_RETRY_AFTER) {
+            return result;
+        }
+
+        // The server knows best when the request can succeed again, the backoff is only a guess
+        let delay = retry_after.unwrap_or_else(|| backoff(&retry, attempt));
+        let reason = match &result {
+            Ok(x) => x.status().to_string(),
+            Err(e) => e.to_string(),
+        };
+        output::info(t!(
+            "api-retry",
+            reason = reason,
+            seconds = format!("{:.1}", delay.as_secs_f64()),
+            attempt = attempt + 1,
+            attempts = retry.attempts
+        ));
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Waits until the request fits into the budget of the rate limit, so that large configurations
+/// are spread over time instead of getting rejected by Cloudflare, each token has its own budget
+async fn throttle(account: &str) {
+    let mut is_throttled = false;
+    loop {
+        let wait = {
+            let budget = *BUDGET.lock().unwrap_or_else(|e| e.into_inner());
+            let mut sent = SENT.lock().unwrap_or_else(|e| e.into_inner());
+            let sent = sent.entry(account.to_owned()).or_default();
+            let now = Instant::now();
+            while sent
+                .front()
+                .is_some_and(|x| now.duration_since(*x) >= RATE_LIMIT_WINDOW)
+            {
+                sent.pop_front();
+            }
+            match sent.front() {
+                Some(oldest)
+                    if budget > 0
+                        && sent.len() >= usize::try_from(budget).unwrap_or(usize::MAX) =>
+                {
+                    RATE_LIMIT_WINDOW - now.duration_since(*oldest)
+                }
+                _ => {
+                    sent.push_back(now);
+                    return;
+                }
+            }
+        };
+
+        if !is_throttled {
+            output::info(t!("api-throttled", seconds = wait.as_secs().max(1)));
+            is_throttled = true;
+        }
+        // A requested shutdown is not delayed by the throttling
+        if shutdown::requested() {
+            return;
+        }
+        sleep(wait.min(Duration::from_secs(1))).await;
+    }
+}
+
+/// Time until the rate limit resets from the Retry-After header (seconds or HTTP date) or the
+/// Ratelimit header of Cloudflare (e.g. `"default";r=0;t=30`)
+fn retry_after(response: &Response) -> Option<Duration> {
+    let headers = response.headers();
+    if let Some(value) = headers
+        .get(header::RETRY_AFTER)
+        .and_then(|x| x.to_str().ok())
+    {
+        if let Ok(seconds) = value.trim().parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        if let Ok(date) = DateTime::parse_from_rfc2822(value.trim()) {
+            return (date.with_timezone(&Utc) - Utc::now()).to_std().ok();
+        }
+    }
+
+    headers
+        .get("Ratelimit")
+        .and_then(|x| x.to_str().ok())?
+        .split(';')
+        .find_map(|x| x.trim().strip_prefix("t=")?.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Doubles the delay with each attempt, a random deviation of up to half of it keeps many instances
+/// from retrying at the same time
+fn backoff(retry: &Retry, attempt: u32) -> Duration {
+    let delay = retry
+        .delay
+        .saturating_mul(2u64.saturating_pow(attempt.saturating_sub(1)))
+        .min(retry.max_delay);
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(delay).mul_f64(0.5 + random as f64 / u64::MAX as f64 / 2.0)
+}
+
+/// Fails for an expired, revoked or mistyped token instead of finding no zones later, without an
+/// answer of Cloudflare (e.g. while offline) the token is left to the further requests
+pub(crate) async fn verify_token(config: &Config) -> Result<(), ErrorKind> {
+    // The Global API Key can't be verified by this endpoint
+    if !config.verify_token || matches!(Auth::of(config), Auth::Key { .. }) {
+        return Ok(());
+    }
+
+    let http = client(config).map_err(|e| ErrorKind::Unknown(Box::new(e)))?;
+    let url = Url::parse("https://api.cloudflare.com/client/v4/user/tokens/verify")
+        .map_err(|e| ErrorKind::Unknown(Box::new(e)))?;
+    let response = match api_get(&http, url, Auth::of(config)).await {
+        Ok(x) if x.status() != 429 && !x.status().is_server_error() => x,
+        _ => return Ok(()),
+    };
+
+    // Cloudflare answers unknown tokens with 401 and code 1000
+    let data = match deserialize_response(response).await {
+        Ok(x) => x,
+        Err(ErrorKind::NoSuccessHttp(x) | ErrorKind::NoSuccessJson(x)) => {
+            return Err(ErrorKind::ApiToken(String::from("invalid"), x))
+        }
+        Err(_) => return Ok(()),
+    };
+    match deserialize_json_value::<VerifyToken>(data.result).await {
+        Ok(x) if x.status != "active" => Err(ErrorKind::ApiToken(x.status, Vec::new())),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) async fn deserialize_response(response: Response) -> Result<Cloudflare, ErrorKind> {
+    if !is_http_success(&response) {
+        return Err(ErrorKind::NoSuccessHttp(api_messages(response).await));
+    }
+
+    let data = response
+        .json::<Cloudflare>()
+        .await
+        .map_err(|_| ErrorKind::Json)?;
+
+    if !data.success {
+        return Err(ErrorKind::NoSuccessJson(
+            data.errors.into_iter().chain(data.messages).collect(),
+        ));
+    }
+
+    Ok(data)
+}
+
+/// Errors and messages of an unsuccessful response, most of them still contain the JSON envelope
+pub(crate) async fn api_messages(response: Response) -> Vec<ApiMessage> {
+    match response.json::<Cloudflare>().await {
+        Ok(x) => x.errors.into_iter().chain(x.messages).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub(crate) async fn deserialize_json_value<T: DeserializeOwned>(
+    data: Json,
+) -> Result<T, ErrorKind> {
+    let result = serde_json::from_value::<T>(data).map_err(|_| ErrorKind::Json)?;
+    Ok(result)
+}
+
+fn is_http_success(response: &Response) -> bool {
+    response.status().is_success()
+}
@@ -0,0 +1,143 @@
+use crate::{
+    api::{
+        api_get, api_get_all, client, deserialize_json_value, deserialize_response, Auth,
+        ZONES_PER_PAGE,
+    },
+    config,
+    errors::{ErrorKind, Failure},
+    i18n::t,
+    ip::{self, IPV4_UNSPECIFIED, IPV6_UNSPECIFIED},
+    structs::{cloudflare::response::ListZone, config::Config},
+    sync::obtain_zone,
+};
+use reqwest::{Client as HttpClient, Url};
+use std::{future::Future, time::Instant};
+
+#[derive(Default)]
+struct Statistics {
+    failures: u32,
+    /// Latencies of the successful attempts in milliseconds
+    samples: Vec<u64>,
+}
+
+impl Statistics {
+    fn line(&self, label: &str) -> String {
+        let total = self.failures as usize + self.samples.len();
+        let (min, avg, max) = match self.samples.is_empty() {
+            true => (String::from("-"), String::from("-"), String::from("-")),
+            false => {
+                let sum = self.samples.iter().sum::<u64>();
+                let count = u64::try_from(self.samples.len()).unwrap_or(u64::MAX);
+                (
+                    self.samples.iter().min().unwrap_or(&0).to_string(),
+                    (sum / count).to_string(),
+                    self.samples.iter().max().unwrap_or(&0).to_string(),
+                )
+            }
+        };
+        format!(
+            "{:<60} {:>9} {:>8} {:>8} {:>8}",
+            label,
+            format!("{}/{}", self.samples.len(), total),
+            min,
+            avg,
+            max
+        )
+    }
+}
+
+/// Measures the latency of all detection sources and representative API calls
+pub(crate) async fn run(config: &Config, iterations: u32) -> Result<(), Failure> {
+    let http: HttpClient =
+        client(config).map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 136))?;
+
+    let api_base: Url = Url::parse("https://api.cloudflare.com/client/v4/")
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 137))?;
+
+    println!(
+        "{:<60} {:>9} {:>8} {:>8} {:>8}",
+        t!("column-target"),
+        t!("column-success"),
+        "min ms",
+        "avg ms",
+        "max ms"
+    );
+
+    for source in ip::sources(config) {
+        let label = source.label();
+        for (family, ip_enum) in [("IPv4", IPV4_UNSPECIFIED), ("IPv6", IPV6_UNSPECIFIED)] {
+            let statistics = measure(iterations, || async {
+                ip::query_source(config, &source, ip_enum, None)
+                    .await
+                    .is_some()
+            })
+            .await;
+            println!("{}", statistics.line(&format!("{label} ({family})")));
+        }
+    }
+
+    let url_list_zones = api_base
+        .join("zones")
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 138))?;
+
+    let statistics = measure(iterations, || async {
+        request(&http, url_list_zones.to_owned(), Auth::of(config)).await
+    })
+    .await;
+    println!("{}", statistics.line("GET zones"));
+
+    let zones = match api_get_all(&http, url_list_zones, Auth::of(config), ZONES_PER_PAGE).await {
+        Ok(Ok(x)) => deserialize_json_value::<Vec<ListZone>>(x.result)
+            .await
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    let zone_names = zones.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+    for config_zone in config::resolve_records(&config.records, &zone_names).keys() {
+        let zone = match obtain_zone(&zones, config_zone).await {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let url_list_dns_records = api_base
+            .join(format!("zones/{}/dns_records", zone.id).as_str())
+            .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 139))?;
+
+        let statistics = measure(iterations, || async {
+            request(&http, url_list_dns_records.to_owned(), Auth::of(config)).await
+        })
+        .await;
+        println!(
+            "{}",
+            statistics.line(&format!("GET zones/{}/dns_records", zone.name))
+        );
+    }
+
+    Ok(())
+}
+
+async fn request(http: &HttpClient, url: Url, auth: Auth<'_>) -> bool {
+    match api_get(http, url, auth).await {
+        Ok(response) => deserialize_response(response).await.is_ok(),
+        Err(_) => false,
+    }
+}
+
+async fn measure<F, Fut>(iterations: u32, attempt: F) -> Statistics
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let mut statistics = Statistics::default();
+    for _ in 0..iterations {
+        let timer = Instant::now();
+        match attempt().await {
+            true => statistics
+                .samples
+                .push(u64::try_from(timer.elapsed().as_millis()).unwrap_or(u64::MAX)),
+            false => statistics.failures += 1,
+        }
+    }
+    statistics
+}
@@ -1,12 +1,17 @@
-use crate::structs::config::Config;
+use crate::structs::{
+    cloudflare::response::ListZone,
+    config::{Config, Records, Spectrum, Target, ZoneSettings},
+};
 use directories::ProjectDirs;
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::{Error as IOError, ErrorKind, Read},
+    net::{Ipv4Addr, Ipv6Addr},
     path::{Path, PathBuf},
 };
 
-fn cargo_name() -> String {
+pub(crate) fn cargo_name() -> String {
     env!("CARGO_PKG_NAME").replace('_', "-")
 }
 
@@ -22,6 +27,196 @@ pub(crate) fn path() -> Result<PathBuf, IOError> {
     Ok(path)
 }
 
+pub(crate) fn record_name(zone: &str, record: &str) -> String {
+    match record == "@" {
+        true => zone.to_owned(),
+        false => format!("{}.{}", record, zone),
+    }
+}
+
+/// Groups the configured records by zone, names without a matching zone are grouped under themselves
+pub(crate) fn resolve_records(records: &Records, zones: &[&str]) -> BTreeMap<String, Vec<Target>> {
+    let mut resolved: BTreeMap<String, Vec<Target>> = BTreeMap::new();
+
+    match records {
+        Records::Names(entries) => {
+            for entry in entries {
+                let name = entry.name().trim_end_matches('.').to_lowercase();
+                let zone = zones
+                    .iter()
+                    .map(|x| x.to_lowercase())
+                    .filter(|x| name == *x || name.ends_with(format!(".{x}").as_str()))
+                    .max_by_key(|x| x.len())
+                    .unwrap_or_else(|| name.to_owned());
+                resolved.entry(zone).or_default().push(Target {
+                    name,
+                    settings: entry.settings(),
+                });
+            }
+        }
+        Records::Zones(map) => {
+            // Names are compared with the ones listed by Cloudflare, e.g. `"Example.com."` is the
+            // same zone as `"example.com"`
+            for (zone, entries) in map {
+                let zone = zone.trim_end_matches('.').to_lowercase();
+                let targets = entries.iter().map(|x| Target {
+                    name: record_name(&zone, x.name().trim_end_matches('.')).to_lowercase(),
+                    settings: x.settings(),
+                });
+                resolved.entry(zone.to_owned()).or_default().extend(targets);
+            }
+        }
+    }
+
+    resolved
+}
+
+/// Configurations of the main account (if it has a token or there are no further accounts) and of
+/// the further accounts, which use their own token, records and zones with the remaining settings
+pub(crate) fn accounts(config: &Config) -> Vec<Config> {
+    let main = Config {
+        accounts: Vec::new(),
+        ..config.to_owned()
+    };
+    let further = config.accounts.iter().map(|x| Config {
+        accounts: Vec::new(),
+        api_email: x.api_email.to_owned(),
+        api_key: x.api_key.to_owned(),
+        api_token: x.api_token.to_owned(),
+        // The heartbeat and the Spectrum applications belong to the main account
+        high_availability: None,
+        records: x.records.to_owned(),
+        spectrum: Spectrum::default(),
+        zones: x.zones.to_owned(),
+        ..main.to_owned()
+    });
+    let is_main_used =
+        !config.api_token.is_empty() || config.api_key.is_some() || config.accounts.is_empty();
+    is_main_used
+        .then_some(main.to_owned())
+        .into_iter()
+        .chain(further)
+        .collect()
+}
+
+/// Records of all accounts, their zones are not resolved
+pub(crate) fn all_records(config: &Config) -> Vec<Target> {
+    accounts(config)
+        .iter()
+        .flat_map(|x| resolve_records(&x.records, &[]).into_values().flatten())
+        .collect()
+}
+
+/// Zones whose ID is configured, e.g. for tokens that are not allowed to list the zones
+pub(crate) fn configured_zones(config: &Config) -> Vec<ListZone> {
+    config
+        .zones
+        .iter()
+        .filter_map(|(name, settings)| {
+            Some(ListZone {
+                id: settings.id.to_owned()?,
+                name: name.trim_end_matches('.').to_lowercase(),
+            })
+        })
+        .collect()
+}
+
+/// Whether the zones have to be listed because a record (or the heartbeat) is in none of the zones
+pub(crate) fn needs_zone_listing(config: &Config, zones: &[ListZone]) -> bool {
+    let names = zones.iter().map(|x| x.name.as_str()).collect::<Vec<_>>();
+    let is_known = |name: &str| {
+        let name = name.trim_end_matches('.').to_lowercase();
+        names
+            .iter()
+            .any(|x| name == *x || name.ends_with(format!(".{x}").as_str()))
+    };
+    resolve_records(&config.records, &names)
+        .keys()
+        .any(|x| !names.contains(&x.as_str()))
+        || config
+            .high_availability
+            .as_ref()
+            .is_some_and(|x| !is_known(&x.record))
+}
+
+/// Settings of the configured zone with the longest name that contains the record
+pub(crate) fn zone_settings<'a>(config: &'a Config, name: &str) -> Option<&'a ZoneSettings> {
+    let name = name.trim_end_matches('.').to_lowercase();
+    config
+        .zones
+        .iter()
+        .filter(|(zone, _)| {
+            let zone = zone.trim_end_matches('.').to_lowercase();
+            name == zone || name.ends_with(format!(".{zone}").as_str())
+        })
+        .max_by_key(|(zone, _)| zone.len())
+        .map(|(_, settings)| settings)
+}
+
+/// Whether a record with this comment is managed by this tool, always without a marker
+pub(crate) fn is_owned(config: &Config, comment: Option<&str>) -> bool {
+    match &config.ownership.marker {
+        Some(marker) => comment.is_some_and(|x| x.split_whitespace().any(|x| x == marker)),
+        None => true,
+    }
+}
+
+/// Comment with the marker appended unless it already contains it
+pub(crate) fn with_marker(config: &Config, comment: Option<String>) -> Option<String> {
+    match (&config.ownership.marker, comment) {
+        (Some(marker), Some(x)) if x.trim().is_empty() => Some(marker.to_owned()),
+        (Some(marker), Some(x)) if !is_owned(config, Some(&x)) => Some(format!("{x} {marker}")),
+        (Some(marker), None) => Some(marker.to_owned()),
+        (_, comment) => comment,
+    }
+}
+
+/// Fixed addresses of a record that bypass the detection
+pub(crate) fn fixed_addresses(
+    config: &Config,
+    target: &Target,
+) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
+    let zone = zone_settings(config, &target.name);
+    (
+        target.settings.ipv4.or_else(|| zone.and_then(|x| x.ipv4)),
+        target.settings.ipv6.or_else(|| zone.and_then(|x| x.ipv6)),
+    )
+}
+
+/// Configured proxy status of a record, records without one keep their status in patch mode
+pub(crate) fn proxied(config: &Config, target: &Target) -> Option<bool> {
+    target
+        .settings
+        .proxied
+        .or_else(|| zone_settings(config, &target.name).and_then(|x| x.proxied))
+}
+
+/// Configured tags of a record, records without any keep their tags in patch mode
+pub(crate) fn tags(config: &Config, target: &Target) -> Option<Vec<String>> {
+    target
+        .settings
+        .tags
+        .to_owned()
+        .or_else(|| zone_settings(config, &target.name).and_then(|x| x.tags.to_owned()))
+}
+
+/// Configured TTL of a record, records without one keep their TTL in patch mode
+pub(crate) fn ttl(config: &Config, target: &Target) -> Option<u32> {
+    target
+        .settings
+        .ttl
+        .or_else(|| zone_settings(config, &target.name).and_then(|x| x.ttl))
+}
+
+/// Seconds between checks of a record in daemon mode
+pub(crate) fn interval(config: &Config, target: &Target, default: u64) -> u64 {
+    target
+        .settings
+        .interval
+        .or_else(|| zone_settings(config, &target.name).and_then(|x| x.interval))
+        .unwrap_or(default)
+}
+
 pub(crate) fn get(path: impl AsRef<Path>) -> Result<Config, IOError> {
     std::fs::create_dir_all(
         path.as_ref()
@@ -0,0 +1,116 @@
+use crate::{
+    daemon::{SharedStatus, Trigger},
+    i18n::t,
+    output,
+    structs::config::Daemon,
+};
+use std::{
+    fs,
+    io::{Error as IOError, ErrorKind},
+    os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt},
+    path::Path,
+    sync::Arc,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::mpsc::Sender,
+};
+
+struct Access {
+    gids: Vec<u32>,
+    /// Owner of the daemon, which is always allowed
+    owner: u32,
+    uids: Vec<u32>,
+}
+
+impl Access {
+    fn allows(&self, uid: u32, gid: u32) -> bool {
+        uid == 0 || uid == self.owner || self.uids.contains(&uid) || self.gids.contains(&gid)
+    }
+}
+
+/// Accepts line-based commands (`sync`, `status`, `reload`) on a Unix domain socket
+pub(crate) fn listen(
+    path: &Path,
+    settings: &Daemon,
+    triggers: Sender<Trigger>,
+    status: SharedStatus,
+) -> Result<(), IOError> {
+    // Remove a stale socket of a previous run, but never any other kind of file
+    match fs::symlink_metadata(path) {
+        Ok(_) if std::os::unix::net::UnixStream::connect(path).is_ok() => {
+            return Err(IOError::from(ErrorKind::AddrInUse))
+        }
+        Ok(x) if x.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => return Err(IOError::from(ErrorKind::AlreadyExists)),
+        Err(_) => {}
+    }
+
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o660))?;
+
+    let access = Arc::new(Access {
+        gids: settings.allowed_gids.to_owned(),
+        owner: fs::metadata(path)?.uid(),
+        uids: settings.allowed_uids.to_owned(),
+    });
+
+    output::info(t!("control-listening", path = path.display()));
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((x, _)) => x,
+                Err(_) => continue,
+            };
+            let access = access.clone();
+            let triggers = triggers.clone();
+            let status = status.clone();
+            tokio::spawn(async move {
+                let _ = handle(stream, &access, &triggers, &status).await;
+            });
+        }
+    });
+
+    Ok(())
+}
+
+async fn handle(
+    stream: UnixStream,
+    access: &Access,
+    triggers: &Sender<Trigger>,
+    status: &SharedStatus,
+) -> Result<(), IOError> {
+    let credentials = stream.peer_cred()?;
+    let (reader, mut writer) = stream.into_split();
+
+    if !access.allows(credentials.uid(), credentials.gid()) {
+        output::error(t!("control-denied", uid = credentials.uid()));
+        return writer.write_all(b"error: permission denied\n").await;
+    }
+
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let response = match line.trim() {
+            "" => continue,
+            "reload" => send(triggers, Trigger::Reload).await,
+            "status" => {
+                let status = status.lock().unwrap_or_else(|e| e.into_inner()).to_owned();
+                serde_json::to_string(&status).map_err(IOError::other)?
+            }
+            "sync" => send(triggers, Trigger::Sync).await,
+            x => format!("error: unknown command \"{x}\""),
+        };
+        writer.write_all(format!("{response}\n").as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+async fn send(triggers: &Sender<Trigger>, trigger: Trigger) -> String {
+    match triggers.send(trigger).await {
+        Ok(_) => String::from("ok"),
+        Err(_) => String::from("error: the daemon is shutting down"),
+    }
+}
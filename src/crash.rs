@@ -0,0 +1,117 @@
+use crate::{i18n::t, output, state, structs::config::Config, VERSION};
+use chrono::Utc;
+use std::{
+    backtrace::Backtrace,
+    env,
+    fmt::Write as FmtWrite,
+    fs,
+    panic::{self, PanicHookInfo},
+    path::PathBuf,
+    sync::Mutex,
+};
+use toml::Value as Toml;
+
+const REDACTED: &str = "<redacted>";
+/// Keys whose values (including nested ones) are removed from the configuration in the bundle
+const SECRET_KEYS: [&str; 5] = ["api_key", "headers", "password", "secret", "token"];
+
+struct Context {
+    config: Option<String>,
+    directory: Option<PathBuf>,
+}
+
+static CONTEXT: Mutex<Context> = Mutex::new(Context {
+    config: None,
+    directory: None,
+});
+
+/// Installs a panic hook that writes a diagnostic bundle in addition to the default output
+pub(crate) fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_bundle(info) {
+            Some(path) => eprintln!("{}", t!("crash-bundle", path = path.display())),
+            None => eprintln!("{}", t!("crash-bundle-failed")),
+        }
+    }));
+}
+
+/// Adds the (redacted) configuration to future bundles and places them next to the state file
+pub(crate) fn set_config(config: &Config) {
+    let redacted = Toml::try_from(config)
+        .map(|mut x| {
+            redact(&mut x, false);
+            x
+        })
+        .and_then(|x| toml::to_string_pretty(&x))
+        .unwrap_or_else(|e| format!("# Could not serialize the configuration: {e}"));
+
+    let directory = state::path(config)
+        .ok()
+        .and_then(|x| x.parent().map(PathBuf::from));
+
+    let mut context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    context.config = Some(redacted);
+    context.directory = directory;
+}
+
+fn redact(value: &mut Toml, secret: bool) {
+    match value {
+        Toml::Table(table) => {
+            for (key, x) in table.iter_mut() {
+                let key = key.to_lowercase();
+                redact(x, secret || SECRET_KEYS.iter().any(|x| key.contains(x)));
+            }
+        }
+        Toml::Array(array) => {
+            for x in array {
+                redact(x, secret);
+            }
+        }
+        x if secret => *x = Toml::String(String::from(REDACTED)),
+        _ => {}
+    }
+}
+
+fn write_bundle(info: &PanicHookInfo) -> Option<PathBuf> {
+    let (config, directory) = match CONTEXT.try_lock() {
+        Ok(x) => (x.config.to_owned(), x.directory.to_owned()),
+        Err(_) => (None, None),
+    };
+
+    let mut bundle = String::new();
+    let _ = writeln!(bundle, "# Diagnostic bundle of {}", env!("CARGO_PKG_NAME"));
+    let _ = writeln!(bundle, "\n## Version\n\n{}", VERSION.unwrap_or("unknown"));
+    let _ = writeln!(
+        bundle,
+        "\n## Target\n\n{} {}",
+        env::consts::OS,
+        env::consts::ARCH
+    );
+    let _ = writeln!(
+        bundle,
+        "\n## Arguments\n\n{}",
+        env::args().collect::<Vec<_>>().join(" ")
+    );
+    let _ = writeln!(bundle, "\n## Panic\n\n{info}");
+    let _ = writeln!(
+        bundle,
+        "\n## Configuration\n\n{}",
+        config.unwrap_or_else(|| String::from("# Not loaded"))
+    );
+    let _ = writeln!(
+        bundle,
+        "\n## Recent log\n\n{}",
+        output::try_recent()
+            .map(|x| x.join("\n"))
+            .unwrap_or_else(|| String::from("# Unavailable"))
+    );
+    let _ = writeln!(bundle, "\n## Backtrace\n\n{}", Backtrace::force_capture());
+
+    let mut path = directory.or_else(|| state::data_dir().ok())?;
+    fs::create_dir_all(&path).ok()?;
+    path.push(format!("crash-{}.txt", Utc::now().format("%Y%m%dT%H%M%SZ")));
+    fs::write(&path, bundle).ok()?;
+    Some(path)
+}
@@ -0,0 +1,457 @@
+use crate::{
+    api, config,
+    errors::{handle_errors, ErrorKind, Failure},
+    i18n::t,
+    output, shutdown,
+    structs::{
+        config::{Config, Daemon, Timezone},
+        report::Report,
+        RunOptions,
+    },
+    sync,
+};
+use chrono::{DateTime, Local, Utc};
+use croner::Cron;
+use serde::Serialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    hash::{BuildHasher, Hasher, RandomState},
+    io::Error as IOError,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{self, Sender},
+    time::{sleep, sleep_until, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Trigger {
+    /// Read the configuration again and check immediately
+    Reload,
+    Shutdown,
+    /// Check immediately instead of waiting for the interval to elapse
+    Sync,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct Status {
+    pub last_check: Option<DateTime<Utc>>,
+    pub next_check: Option<DateTime<Utc>>,
+    /// Report of the last check
+    pub report: Option<Report>,
+}
+
+pub(crate) type SharedStatus = Arc<Mutex<Status>>;
+
+pub(crate) async fn run(
+    config_path: &Path,
+    mut config: Config,
+    options: &RunOptions,
+    interval: Option<u64>,
+    startup_delay: u64,
+) -> Result<(), Failure> {
+    let (triggers, mut receiver) = mpsc::channel::<Trigger>(16);
+    let status = SharedStatus::default();
+
+    #[cfg(unix)]
+    if let Some(path) = &config.daemon.socket {
+        crate::control::listen(path, &config.daemon, triggers.clone(), status.clone())
+            .map_err(|e| Failure::new(ErrorKind::Control(e), 140))?;
+    }
+
+    #[cfg(not(unix))]
+    if config.daemon.socket.is_some() {
+        output::error(t!("control-unsupported"));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+    if config.daemon.events {
+        if let Err(e) = crate::events::listen(&config.daemon.event_interfaces, triggers.clone()) {
+            handle_errors(&ErrorKind::Events(e));
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+    if config.daemon.events {
+        output::error(t!("events-unsupported"));
+    }
+
+    if config.daemon.watch_config {
+        if let Err(e) = spawn_config_watcher(config_path, triggers.clone()) {
+            handle_errors(&ErrorKind::ConfigWatch(e));
+        }
+    }
+
+    spawn_shutdown_handler(triggers.clone());
+    #[cfg(unix)]
+    spawn_signal_handler(triggers.clone());
+
+    crate::startup::wait(&config, startup_delay).await;
+    for account in config::accounts(&config) {
+        api::verify_token(&account)
+            .await
+            .map_err(|e| Failure::new(e, 158))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    let busy = crate::systemd::Busy::default();
+    #[cfg(target_os = "linux")]
+    crate::systemd::spawn_watchdog(busy.clone());
+    let mut is_ready = false;
+    // Only the first check discards the cached IDs
+    let mut refresh_cache = options.refresh_cache;
+
+    // Next check of each record, records without an entry are due
+    let mut schedule: HashMap<String, Instant> = HashMap::new();
+
+    // The first check waits for the first occurrence of the schedule as well, unless it is triggered
+    // earlier (e.g. by an address change)
+    if let Some(seconds) = interval
+        .is_none()
+        .then(|| scheduled(&config.daemon))
+        .flatten()
+    {
+        #[cfg(target_os = "linux")]
+        {
+            let _ = crate::systemd::notify("READY=1");
+            is_ready = true;
+        }
+        tokio::select! {
+            _ = sleep(Duration::from_secs(seconds)) => {}
+            x = receiver.recv() => match x.unwrap_or(Trigger::Shutdown) {
+                Trigger::Shutdown => {
+                    stop(&config);
+                    return Ok(());
+                }
+                // Handled after the check, which is why the trigger is queued again
+                Trigger::Reload => {
+                    let _ = triggers.try_send(Trigger::Reload);
+                }
+                Trigger::Sync => {}
+            },
+        }
+    }
+
+    loop {
+        let scheduled = interval
+            .is_none()
+            .then(|| scheduled(&config.daemon))
+            .flatten();
+        let default_interval = interval.or(scheduled).unwrap_or(config.daemon.interval);
+        // The occurrences of a schedule are kept exactly
+        let jitter = match scheduled {
+            Some(_) => 0,
+            None => config.daemon.jitter,
+        };
+        let now = Instant::now();
+        let targets = config::accounts(&config)
+            .iter()
+            .flat_map(|account| {
+                config::resolve_records(&account.records, &[])
+                    .into_values()
+                    .flatten()
+                    .map(|x| {
+                        let seconds = config::interval(account, &x, default_interval);
+                        (x.name, seconds)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let due = targets
+            .iter()
+            .filter(|(name, _)| schedule.get(name).is_none_or(|x| *x <= now))
+            .collect::<Vec<_>>();
+
+        let run_options = RunOptions {
+            records: match due.len() == targets.len() {
+                true => None,
+                false => Some(due.iter().map(|(name, _)| name.to_owned()).collect()),
+            },
+            refresh_cache,
+            ..options.to_owned()
+        };
+        refresh_cache = false;
+        #[cfg(target_os = "linux")]
+        {
+            *busy.lock().unwrap_or_else(|e| e.into_inner()) = Some(std::time::Instant::now());
+        }
+        let result = match crate::lock::acquire(&config, options.wait)
+            .await
+            .map_err(|e| Failure::new(ErrorKind::Lock(e), 150))?
+        {
+            Some(_lock) => Some(sync::run(&config, &run_options).await),
+            None => {
+                output::info(t!("lock-busy"));
+                None
+            }
+        };
+        #[cfg(target_os = "linux")]
+        {
+            *busy.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+
+        for (name, seconds) in &due {
+            let delay = jittered(*seconds, jitter);
+            schedule.insert(name.to_owned(), now + delay);
+        }
+        schedule.retain(|name, _| targets.iter().any(|(x, _)| x == name));
+        let next_check = schedule
+            .values()
+            .min()
+            .copied()
+            .unwrap_or_else(|| now + jittered(default_interval, jitter));
+
+        {
+            let mut status = status.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Utc::now();
+            status.last_check = Some(now);
+            status.next_check = chrono::Duration::from_std(next_check - Instant::now())
+                .ok()
+                .map(|x| now + x);
+            match result {
+                Some(Ok(report)) => {
+                    notify_service(&report, !is_ready);
+                    is_ready = true;
+                    status.report = Some(report);
+                }
+                Some(Err(e)) => handle_errors(&e.kind),
+                None => {}
+            }
+        }
+
+        let trigger = tokio::select! {
+            _ = sleep_until(next_check) => None,
+            x = receiver.recv() => Some(x.unwrap_or(Trigger::Shutdown)),
+        };
+
+        // Explicit triggers check all records
+        let mut trigger = match trigger {
+            Some(x) => {
+                schedule.clear();
+                x
+            }
+            None => continue,
+        };
+
+        // Triggers that arrived during the check (e.g. repeated signals) result in a single check
+        while let Ok(x) = receiver.try_recv() {
+            trigger = coalesce(trigger, x);
+        }
+
+        match trigger {
+            Trigger::Reload => match config::get(config_path) {
+                Ok(x) => {
+                    api::configure(&x);
+                    if let Err(e) = output::init(&x.log) {
+                        handle_errors(&ErrorKind::Log(e));
+                    }
+                    let (before, after) = (record_names(&config), record_names(&x));
+                    for name in after.difference(&before) {
+                        output::info(t!("daemon-record-added", name = name));
+                    }
+                    for name in before.difference(&after) {
+                        output::info(t!("daemon-record-removed", name = name));
+                    }
+                    config = x;
+                    output::info(t!("daemon-reloaded"));
+                }
+                Err(e) => {
+                    handle_errors(&ErrorKind::Config(e));
+                    output::error(t!("daemon-reload-failed"));
+                }
+            },
+            Trigger::Shutdown => break,
+            Trigger::Sync => {}
+        }
+    }
+
+    stop(&config);
+    Ok(())
+}
+
+/// Removes the control socket and tells systemd that the daemon is stopping
+fn stop(config: &Config) {
+    #[cfg(unix)]
+    if let Some(path) = &config.daemon.socket {
+        let _ = std::fs::remove_file(path);
+    }
+    #[cfg(not(unix))]
+    let _ = config;
+
+    #[cfg(target_os = "linux")]
+    let _ = crate::systemd::notify("STOPPING=1");
+}
+
+/// Reports readiness after the first successful check and the detected addresses to systemd
+#[cfg(target_os = "linux")]
+fn notify_service(report: &Report, ready: bool) {
+    let address = |x: Option<String>| x.unwrap_or_else(|| String::from("-"));
+    let status = t!(
+        "systemd-status",
+        ipv4 = address(report.ipv4.map(|x| x.to_string())),
+        ipv6 = address(report.ipv6.map(|x| x.to_string()))
+    );
+    let state = match ready {
+        true => format!("READY=1\nSTATUS={status}"),
+        false => format!("STATUS={status}"),
+    };
+    let _ = crate::systemd::notify(&state);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn notify_service(_: &Report, _: bool) {}
+
+/// Seconds until the next occurrence of the schedule, None if there is no valid schedule
+fn scheduled(daemon: &Daemon) -> Option<u64> {
+    let schedule = daemon.schedule.as_ref()?;
+    let cron = match Cron::from_str(schedule) {
+        Ok(x) => x,
+        Err(e) => {
+            output::error(t!(
+                "daemon-schedule-invalid",
+                schedule = schedule,
+                error = e
+            ));
+            return None;
+        }
+    };
+
+    let next = match daemon.timezone {
+        Timezone::Local => cron
+            .find_next_occurrence(&Local::now(), false)
+            .ok()?
+            .with_timezone(&Utc),
+        Timezone::Utc => cron.find_next_occurrence(&Utc::now(), false).ok()?,
+    };
+    // Rounded up to not wake up right before the occurrence
+    let milliseconds = (next - Utc::now()).num_milliseconds();
+    Some(
+        u64::try_from((milliseconds + 999) / 1000)
+            .unwrap_or(0)
+            .max(1),
+    )
+}
+
+/// Deviates randomly from the interval by up to the given percentage in both directions
+fn jittered(seconds: u64, percent: u8) -> Duration {
+    let interval = Duration::from_secs(seconds);
+    if percent == 0 {
+        return interval;
+    }
+
+    // The keys of RandomState are seeded randomly, which is sufficient to spread the checks
+    let random = RandomState::new().build_hasher().finish();
+    let factor =
+        (random as f64 / u64::MAX as f64 * 2.0 - 1.0) * f64::from(percent.min(100)) / 100.0;
+    interval.mul_f64(1.0 + factor)
+}
+
+fn coalesce(a: Trigger, b: Trigger) -> Trigger {
+    match (a, b) {
+        (Trigger::Shutdown, _) | (_, Trigger::Shutdown) => Trigger::Shutdown,
+        (Trigger::Reload, _) | (_, Trigger::Reload) => Trigger::Reload,
+        (Trigger::Sync, Trigger::Sync) => Trigger::Sync,
+    }
+}
+
+fn record_names(config: &Config) -> BTreeSet<String> {
+    config::all_records(config)
+        .into_iter()
+        .map(|x| x.name)
+        .collect()
+}
+
+/// Editors often replace the file instead of writing it, so its directory is watched
+fn spawn_config_watcher(config_path: &Path, triggers: Sender<Trigger>) -> Result<(), IOError> {
+    use notify::{RecursiveMode, Watcher};
+
+    let path = std::path::absolute(config_path)?;
+    let directory = path
+        .parent()
+        .ok_or_else(|| IOError::from(std::io::ErrorKind::NotFound))?
+        .to_owned();
+
+    let (sender, mut receiver) = mpsc::channel::<()>(1);
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let is_config = event.is_ok_and(|x| {
+            !x.kind.is_access() && x.paths.iter().any(|x| x.file_name() == path.file_name())
+        });
+        if is_config {
+            let _ = sender.try_send(());
+        }
+    })
+    .map_err(IOError::other)?;
+    watcher
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .map_err(IOError::other)?;
+
+    tokio::spawn(async move {
+        // The watcher stops when it is dropped
+        let _watcher = watcher;
+        while receiver.recv().await.is_some() {
+            // Saving a file often results in several events
+            sleep(Duration::from_secs(1)).await;
+            while receiver.try_recv().is_ok() {}
+
+            output::info(t!("config-changed"));
+            if triggers.send(Trigger::Reload).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn spawn_shutdown_handler(triggers: Sender<Trigger>) {
+    tokio::spawn(async move {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            shutdown::request();
+            let _ = triggers.send(Trigger::Shutdown).await;
+        }
+    });
+}
+
+/// SIGUSR1 triggers an immediate check (e.g. from ppp ip-up scripts), SIGHUP reloads the
+/// configuration and SIGTERM stops the daemon
+#[cfg(unix)]
+fn spawn_signal_handler(triggers: Sender<Trigger>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (mut usr1, mut hup, mut term) = match (
+        signal(SignalKind::user_defined1()),
+        signal(SignalKind::hangup()),
+        signal(SignalKind::terminate()),
+    ) {
+        (Ok(x), Ok(y), Ok(z)) => (x, y, z),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            handle_errors(&ErrorKind::Signal(e));
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        loop {
+            let trigger = tokio::select! {
+                _ = usr1.recv() => {
+                    output::info(t!("signal-sync"));
+                    Trigger::Sync
+                }
+                _ = hup.recv() => {
+                    output::info(t!("signal-reload"));
+                    Trigger::Reload
+                }
+                _ = term.recv() => {
+                    shutdown::request();
+                    Trigger::Shutdown
+                }
+            };
+            if triggers.send(trigger).await.is_err() {
+                break;
+            }
+        }
+    });
+}
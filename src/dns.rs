@@ -0,0 +1,338 @@
+use crate::{
+    api, fixtures,
+    i18n::t,
+    structs::config::{Config, Resolver, TlsResolver},
+};
+use reqwest::header;
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{Error as IOError, ErrorKind as IOErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UdpSocket},
+    time::timeout,
+};
+use tokio_rustls::{
+    rustls::{self, pki_types::ServerName, RootCertStore},
+    TlsConnector,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const UDP_BUFFER_SIZE: usize = 4096;
+const MEDIA_TYPE: &str = "application/dns-message";
+
+const TYPE_A: u16 = 1;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RecordType {
+    A,
+    Aaaa,
+    Txt,
+}
+
+impl RecordType {
+    fn code(self) -> u16 {
+        match self {
+            Self::A => TYPE_A,
+            Self::Aaaa => TYPE_AAAA,
+            Self::Txt => TYPE_TXT,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Answer {
+    pub data: Vec<u8>,
+    pub type_: u16,
+}
+
+impl Answer {
+    pub(crate) fn address(&self) -> Option<IpAddr> {
+        match self.type_ {
+            TYPE_A => <[u8; 4]>::try_from(self.data.as_slice())
+                .ok()
+                .map(|x| IpAddr::V4(Ipv4Addr::from(x))),
+            TYPE_AAAA => <[u8; 16]>::try_from(self.data.as_slice())
+                .ok()
+                .map(|x| IpAddr::V6(Ipv6Addr::from(x))),
+            _ => None,
+        }
+    }
+
+    /// Concatenated character strings of a TXT record
+    pub(crate) fn text(&self) -> Option<String> {
+        if self.type_ != TYPE_TXT {
+            return None;
+        }
+
+        let mut text = Vec::new();
+        let mut data = self.data.as_slice();
+        while let Some((length, rest)) = data.split_first() {
+            let (string, rest) = rest.split_at_checked(usize::from(*length))?;
+            text.extend_from_slice(string);
+            data = rest;
+        }
+        String::from_utf8(text).ok()
+    }
+}
+
+/// Resolves the addresses of a name using the configured resolver
+pub(crate) async fn lookup(
+    config: &Config,
+    name: &str,
+    type_: RecordType,
+) -> Result<Vec<IpAddr>, IOError> {
+    let answers = query(config, &config.dns.resolver, name, type_).await?;
+    Ok(answers.iter().filter_map(Answer::address).collect())
+}
+
+pub(crate) async fn query(
+    config: &Config,
+    resolver: &Resolver,
+    name: &str,
+    type_: RecordType,
+) -> Result<Vec<Answer>, IOError> {
+    // RFC 8484 recommends an ID of 0 for DoH to make responses cacheable
+    let id = match resolver {
+        Resolver::Https(_) => 0,
+        _ => query_id(),
+    };
+    let message = encode_query(id, name, type_)?;
+
+    let response = match resolver {
+        Resolver::Https(url) => query_https(config, url, &message).await?,
+        Resolver::Tls(x) => query_tls(x, &message).await?,
+        Resolver::Udp(x) => query_udp(*x, None, None, &message).await?,
+    };
+
+    decode_response(id, &response)
+}
+
+/// Queries a server directly over UDP, optionally from a local address or interface (Linux)
+pub(crate) async fn query_server(
+    server: SocketAddr,
+    local_address: Option<IpAddr>,
+    interface: Option<&str>,
+    name: &str,
+    type_: RecordType,
+) -> Result<Vec<Answer>, IOError> {
+    let id = query_id();
+    let message = encode_query(id, name, type_)?;
+    let response = query_udp(server, local_address, interface, &message).await?;
+    decode_response(id, &response)
+}
+
+async fn query_udp(
+    address: SocketAddr,
+    local_address: Option<IpAddr>,
+    interface: Option<&str>,
+    message: &[u8],
+) -> Result<Vec<u8>, IOError> {
+    let local_address = match (address, local_address) {
+        (_, Some(x)) => SocketAddr::new(x, 0),
+        (SocketAddr::V4(_), None) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        (SocketAddr::V6(_), None) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+    let socket = UdpSocket::bind(local_address).await?;
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    if let Some(interface) = interface {
+        socket.bind_device(Some(interface.as_bytes()))?;
+    }
+    #[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+    let _ = interface;
+    socket.connect(address).await?;
+    socket.send(message).await?;
+
+    let mut buffer = vec![0; UDP_BUFFER_SIZE];
+    let length = timeout(TIMEOUT, socket.recv(&mut buffer))
+        .await
+        .map_err(|_| IOError::from(IOErrorKind::TimedOut))??;
+    buffer.truncate(length);
+    Ok(buffer)
+}
+
+async fn query_tls(resolver: &TlsResolver, message: &[u8]) -> Result<Vec<u8>, IOError> {
+    let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let tls_config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(IOError::other)?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+
+    let server_name = ServerName::try_from(resolver.server_name.to_owned())
+        .map_err(|e| IOError::new(IOErrorKind::InvalidInput, e))?;
+    let length =
+        u16::try_from(message.len()).map_err(|e| IOError::new(IOErrorKind::InvalidInput, e))?;
+
+    // Messages over TCP are prefixed with their length (RFC 1035 section 4.2.2)
+    let exchange = async {
+        let stream = TcpStream::connect(resolver.address).await?;
+        let mut stream = TlsConnector::from(Arc::new(tls_config))
+            .connect(server_name, stream)
+            .await?;
+        stream
+            .write_all(&[&length.to_be_bytes(), message].concat())
+            .await?;
+
+        let length = stream.read_u16().await?;
+        let mut buffer = vec![0; usize::from(length)];
+        stream.read_exact(&mut buffer).await?;
+        Ok(buffer)
+    };
+
+    timeout(TIMEOUT, exchange)
+        .await
+        .map_err(|_| IOError::from(IOErrorKind::TimedOut))?
+}
+
+async fn query_https(config: &Config, url: &str, message: &[u8]) -> Result<Vec<u8>, IOError> {
+    let http = api::client_builder(config, false)
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(IOError::other)?;
+
+    let request = http
+        .post(url)
+        .header(header::ACCEPT, MEDIA_TYPE)
+        .header(header::CONTENT_TYPE, MEDIA_TYPE)
+        .body(message.to_vec());
+
+    let response = fixtures::send(request)
+        .await
+        .and_then(|x| x.error_for_status())
+        .map_err(IOError::other)?;
+
+    let body = response.bytes().await.map_err(IOError::other)?;
+    Ok(body.to_vec())
+}
+
+fn query_id() -> u16 {
+    // The keys of RandomState are seeded randomly, which is sufficient to make IDs unpredictable
+    let hash = RandomState::new().build_hasher().finish();
+    hash.to_be_bytes()
+        .chunks(2)
+        .fold(0, |id, x| id ^ u16::from_be_bytes([x[0], x[1]]))
+}
+
+fn encode_query(id: u16, name: &str, type_: RecordType) -> Result<Vec<u8>, IOError> {
+    let mut message = Vec::with_capacity(name.len() + 18);
+    message.extend_from_slice(&id.to_be_bytes());
+    // Standard query with recursion desired
+    message.extend_from_slice(&0x0100u16.to_be_bytes());
+    // One question, no answer, authority and additional records
+    message.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+
+    for label in name.trim_end_matches('.').split('.') {
+        let length = u8::try_from(label.len())
+            .ok()
+            .filter(|x| (1..=63).contains(x))
+            .ok_or_else(|| {
+                IOError::new(
+                    IOErrorKind::InvalidInput,
+                    t!("dns-invalid-name", name = name),
+                )
+            })?;
+        message.push(length);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+
+    message.extend_from_slice(&type_.code().to_be_bytes());
+    message.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(message)
+}
+
+fn decode_response(id: u16, message: &[u8]) -> Result<Vec<Answer>, IOError> {
+    let mut reader = Reader {
+        message,
+        position: 0,
+    };
+
+    let response_id = reader.u16()?;
+    let flags = reader.u16()?;
+    let questions = reader.u16()?;
+    let answers = reader.u16()?;
+    reader.skip(4)?;
+
+    if response_id != id || flags & 0x8000 == 0 {
+        return Err(IOError::new(
+            IOErrorKind::InvalidData,
+            t!("dns-unexpected-response"),
+        ));
+    }
+    if flags & 0x0200 != 0 {
+        return Err(IOError::new(
+            IOErrorKind::InvalidData,
+            t!("dns-truncated-response"),
+        ));
+    }
+    match flags & 0x000f {
+        0 => {}
+        // NXDOMAIN, the name does not exist
+        3 => return Ok(Vec::new()),
+        x => return Err(IOError::other(t!("dns-error-code", code = x))),
+    }
+
+    for _ in 0..questions {
+        reader.skip_name()?;
+        reader.skip(4)?;
+    }
+
+    let mut result = Vec::new();
+    for _ in 0..answers {
+        reader.skip_name()?;
+        let type_ = reader.u16()?;
+        // Class and TTL
+        reader.skip(6)?;
+        let length = reader.u16()?;
+        let data = reader.take(usize::from(length))?.to_vec();
+        result.push(Answer { data, type_ });
+    }
+    Ok(result)
+}
+
+struct Reader<'a> {
+    message: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], IOError> {
+        let data = self
+            .message
+            .get(self.position..self.position + length)
+            .ok_or_else(|| IOError::new(IOErrorKind::InvalidData, t!("dns-malformed-response")))?;
+        self.position += length;
+        Ok(data)
+    }
+
+    fn skip(&mut self, length: usize) -> Result<(), IOError> {
+        self.take(length).map(|_| ())
+    }
+
+    fn u16(&mut self) -> Result<u16, IOError> {
+        let data = self.take(2)?;
+        Ok(u16::from_be_bytes([data[0], data[1]]))
+    }
+
+    fn skip_name(&mut self) -> Result<(), IOError> {
+        loop {
+            match self.take(1)?[0] {
+                0 => return Ok(()),
+                // A compression pointer always ends the name
+                x if x & 0xc0 == 0xc0 => return self.skip(1),
+                x => self.skip(usize::from(x))?,
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    config,
+    errors::{ErrorKind, Failure},
+    i18n::t,
+    lock, output,
+    server::{self, Request, Response},
+    structs::{config::Config, report::Action, RunOptions},
+    sync,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::net::TcpListener;
+
+/// Accepts updates of the DynDNS2 protocol (`/nic/update?hostname=...&myip=...`) that the DDNS
+/// clients of most routers speak, the records are updated right away instead of polling
+pub(crate) async fn run(
+    config: Config,
+    options: &RunOptions,
+    listen: SocketAddr,
+) -> Result<(), Failure> {
+    if config.dyndns.username.is_none() || config.dyndns.password.is_none() {
+        return Err(Failure::new(ErrorKind::DyndnsCredentials, 153));
+    }
+
+    let listener = TcpListener::bind(listen)
+        .await
+        .map_err(|e| Failure::new(ErrorKind::Server(e), 154))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| Failure::new(ErrorKind::Server(e), 154))?;
+    output::info(t!("dyndns-serving", address = address));
+
+    let config = Arc::new(config);
+    let options = Arc::new(options.to_owned());
+    server::serve(listener, move |request| {
+        let config = config.clone();
+        let options = options.clone();
+        async move { handle(&config, &options, request).await }
+    })
+    .await
+    .map_err(|e| Failure::new(ErrorKind::Server(e), 154))
+}
+
+async fn handle(config: &Config, options: &RunOptions, request: Request) -> Response {
+    if request.method != "GET" {
+        return Response::text(405, "Method Not Allowed\n");
+    }
+    if !matches!(request.path(), "/nic/update" | "/update") {
+        return Response::text(404, "Not Found\n");
+    }
+    if !is_authorized(config, &request) {
+        let mut response = Response::text(401, "badauth\n");
+        response.headers.push((
+            String::from("WWW-Authenticate"),
+            String::from("Basic realm=\"ddns-cloudflare\""),
+        ));
+        return response;
+    }
+
+    let parameters = parameters(&request);
+    let hostnames = parameters
+        .iter()
+        .filter(|(key, _)| key == "hostname")
+        .flat_map(|(_, value)| value.split(','))
+        .map(|x| x.trim().trim_end_matches('.').to_lowercase())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>();
+    if hostnames.is_empty() {
+        return Response::text(200, "notfqdn\n");
+    }
+
+    let configured = config::all_records(config)
+        .into_iter()
+        .map(|x| x.name)
+        .collect::<HashSet<_>>();
+    if hostnames.iter().any(|x| !configured.contains(x)) {
+        return Response::text(200, "nohost\n");
+    }
+
+    // Without an address the one of the client is used, e.g. if the router reaches this server
+    // through the internet
+    let mut addresses = parameters
+        .iter()
+        .filter(|(key, _)| key == "myip" || key == "myipv6")
+        .flat_map(|(_, value)| value.split(','))
+        .filter_map(|x| IpAddr::from_str(x.trim()).ok())
+        .map(|x| x.to_canonical())
+        .collect::<Vec<_>>();
+    if addresses.is_empty() {
+        addresses.push(request.peer.ip().to_canonical());
+    }
+    if addresses
+        .iter()
+        .any(|x| !x.is_global() && !config.detection.allow_private)
+    {
+        return Response::text(200, "dnserr\n");
+    }
+
+    let ipv4 = addresses.iter().find_map(|x| match x {
+        IpAddr::V4(x) => Some(*x),
+        IpAddr::V6(_) => None,
+    });
+    let ipv6 = addresses.iter().find_map(|x| match x {
+        IpAddr::V4(_) => None,
+        IpAddr::V6(x) => Some(*x),
+    });
+    output::info(t!(
+        "dyndns-update",
+        hostnames = hostnames.join(", "),
+        addresses = addresses
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        peer = request.peer.ip().to_canonical()
+    ));
+
+    let options = RunOptions {
+        ipv4,
+        ipv6,
+        records: Some(hostnames.iter().cloned().collect()),
+        wait: true,
+        ..options.to_owned()
+    };
+    let report = match lock::acquire(config, true).await {
+        Ok(Some(_lock)) => sync::run(config, &options).await,
+        _ => return Response::text(200, "911\n"),
+    };
+    let report = match report {
+        Ok(x) => x,
+        Err(_) => return Response::text(200, "911\n"),
+    };
+
+    let content = addresses
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let body = hostnames
+        .iter()
+        .map(|hostname| {
+            let actions = report
+                .records
+                .iter()
+                .filter(|x| x.name.eq_ignore_ascii_case(hostname))
+                .map(|x| x.action)
+                .collect::<Vec<_>>();
+            if actions.contains(&Action::Failed) {
+                String::from("911\n")
+            } else if actions.iter().all(|x| *x == Action::NotFound) {
+                String::from("nohost\n")
+            } else if actions
+                .iter()
+                .any(|x| matches!(x, Action::Created | Action::Updated))
+            {
+                format!("good {content}\n")
+            } else {
+                format!("nochg {content}\n")
+            }
+        })
+        .collect::<String>();
+    Response::text(200, body)
+}
+
+fn is_authorized(config: &Config, request: &Request) -> bool {
+    let (Some(username), Some(password)) = (&config.dyndns.username, &config.dyndns.password)
+    else {
+        return false;
+    };
+
+    let credentials = request
+        .header("Authorization")
+        .and_then(|x| x.strip_prefix("Basic "))
+        .and_then(|x| STANDARD.decode(x.trim()).ok())
+        .and_then(|x| String::from_utf8(x).ok());
+    credentials.is_some_and(|x| x == format!("{username}:{password}"))
+}
+
+/// Decoded query parameters of the request target
+fn parameters(request: &Request) -> Vec<(String, String)> {
+    let query = match request.target.split_once('?') {
+        Some((_, query)) => query,
+        None => return Vec::new(),
+    };
+    query
+        .split('&')
+        .filter_map(|x| {
+            let (key, value) = x.split_once('=').unwrap_or((x, ""));
+            Some((decode(key)?, decode(value)?))
+        })
+        .collect()
+}
+
+/// Percent-decoding of a query component, `+` stands for a space
+fn decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            x => {
+                decoded.push(x);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,67 @@
+use crate::{
+    i18n::t,
+    output,
+    server::{self, Request, Response},
+};
+use serde_json::json;
+use std::{
+    io::Error as IOError,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+};
+use tokio::net::TcpListener;
+
+pub(crate) struct Settings {
+    pub listen: SocketAddr,
+    pub proxy_header: Option<String>,
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+pub(crate) async fn run(settings: Settings) -> Result<(), IOError> {
+    let listener = TcpListener::bind(settings.listen).await?;
+    output::info(t!("echo-serving", address = listener.local_addr()?));
+
+    let settings = Arc::new(settings);
+    server::serve(listener, move |request| {
+        let settings = settings.clone();
+        async move { handle(&settings, request) }
+    })
+    .await
+}
+
+fn handle(settings: &Settings, request: Request) -> Response {
+    if request.method != "GET" {
+        return Response::text(405, "Method Not Allowed\n");
+    }
+
+    let ip = client_ip(settings, &request);
+
+    let wants_json = request.path() == "/json"
+        || request
+            .header("Accept")
+            .is_some_and(|x| x.contains("application/json"));
+
+    match request.path() {
+        "/" | "/json" if wants_json => Response::json(200, json!({ "ip": ip }).to_string()),
+        "/" => Response::text(200, format!("{ip}\n")),
+        _ => Response::text(404, "Not Found\n"),
+    }
+}
+
+/// The proxy header is only honored for requests coming from a trusted proxy
+fn client_ip(settings: &Settings, request: &Request) -> IpAddr {
+    let peer = request.peer.ip().to_canonical();
+
+    let header = match &settings.proxy_header {
+        Some(x) if settings.trusted_proxies.contains(&peer) => x,
+        _ => return peer,
+    };
+
+    request
+        .header(header)
+        .and_then(|x| x.rsplit(',').next())
+        .and_then(|x| IpAddr::from_str(x.trim()).ok())
+        .map(|x| x.to_canonical())
+        .unwrap_or(peer)
+}
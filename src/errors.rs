@@ -1,36 +1,93 @@
-use std::{error::Error, io::Error as IOError};
+use crate::{i18n::t, output, structs::cloudflare::ApiMessage};
+use std::{error::Error, io::Error as IOError, net::IpAddr};
 
 #[derive(Debug)]
 pub(crate) enum ErrorKind {
     Api,
+    ApiToken(String, Vec<ApiMessage>),
     Config(IOError),
     ConfigPath(IOError),
+    ConfigWatch(IOError),
+    Control(IOError),
+    DyndnsCredentials,
+    Events(IOError),
+    Fixtures(IOError),
     IPv4,
     IPv6,
     Json,
+    Lock(IOError),
+    Log(IOError),
+    NoIp,
     NonAddressRecord,
-    NoSuccessHttp,
-    NoSuccessJson,
-    Unknown(Box<dyn Error>),
+    NoSuccessHttp(Vec<ApiMessage>),
+    NoSuccessJson(Vec<ApiMessage>),
+    ProvidedAddress(IpAddr),
+    Report(IOError),
+    SelfUpdate(IOError),
+    Server(IOError),
+    Service(IOError),
+    Signal(IOError),
+    State(IOError),
+    Terminal(IOError),
+    Unknown(Box<dyn Error + Send + Sync>),
+}
+
+#[derive(Debug)]
+pub(crate) struct Failure {
+    pub code: i32,
+    pub kind: ErrorKind,
+}
+
+impl Failure {
+    pub(crate) fn new(kind: ErrorKind, code: i32) -> Self {
+        Self { code, kind }
+    }
 }
 
 pub(crate) fn handle_errors(kind: &ErrorKind) {
+    output::error(message(kind));
+}
+
+pub(crate) fn message(kind: &ErrorKind) -> String {
     match kind {
-        ErrorKind::Api => println!("The HTTP client encountered an unexpected error while trying to connect to the API"),
-        ErrorKind::Config(e) => println!("An error occurred while parsing the configuration. Please consult the readme for an example configuration.\n{e}"),
-        ErrorKind::ConfigPath(e) => println!("An error occurred while trying to get the path to the configuration file.\n{e}"),
-        ErrorKind::IPv4 => println!("An error occurred while trying to determine the IPv4 address"),
-        ErrorKind::IPv6 => println!("An error occurred while trying to determine the IPv6 address"),
-        ErrorKind::Json => println!("An error occurred while (de)serializing JSON"),
-        ErrorKind::NonAddressRecord => println!(
-            "Encountered a record that was not of type \"A\" or \"AAAA\""
-        ),
-        ErrorKind::NoSuccessHttp => println!(
-            "A HTTP response was unsuccessful (status code not between 200-299)"
-        ),
-        ErrorKind::NoSuccessJson => println!(
-            "A JSON response contained invalid data (missing {{ \"success\": true }})"
-        ),
-        ErrorKind::Unknown(e) => println!("An unexpected error occured!\n{e}"),
-    };
+        ErrorKind::Api => t!("error-api"),
+        ErrorKind::ApiToken(status, x) => {
+            with_api_messages(t!("error-api-token", status = status), x)
+        }
+        ErrorKind::Config(e) => format!("{}\n{e}", t!("error-config")),
+        ErrorKind::ConfigPath(e) => format!("{}\n{e}", t!("error-config-path")),
+        ErrorKind::ConfigWatch(e) => format!("{}\n{e}", t!("error-config-watch")),
+        ErrorKind::Control(e) => format!("{}\n{e}", t!("error-control")),
+        ErrorKind::DyndnsCredentials => t!("error-dyndns-credentials"),
+        ErrorKind::Events(e) => format!("{}\n{e}", t!("error-events")),
+        ErrorKind::Fixtures(e) => format!("{}\n{e}", t!("error-fixtures")),
+        ErrorKind::IPv4 => t!("error-ipv4"),
+        ErrorKind::IPv6 => t!("error-ipv6"),
+        ErrorKind::Json => t!("error-json"),
+        ErrorKind::Lock(e) => format!("{}\n{e}", t!("error-lock")),
+        ErrorKind::Log(e) => format!("{}\n{e}", t!("error-log")),
+        ErrorKind::NoIp => t!("error-no-ip"),
+        ErrorKind::NonAddressRecord => t!("error-non-address-record"),
+        ErrorKind::NoSuccessHttp(x) => with_api_messages(t!("error-no-success-http"), x),
+        ErrorKind::NoSuccessJson(x) => with_api_messages(t!("error-no-success-json"), x),
+        ErrorKind::ProvidedAddress(ip) => t!("error-provided-address", ip = ip),
+        ErrorKind::Report(e) => format!("{}\n{e}", t!("error-report")),
+        ErrorKind::SelfUpdate(e) => format!("{}\n{e}", t!("error-self-update")),
+        ErrorKind::Server(e) => format!("{}\n{e}", t!("error-server")),
+        ErrorKind::Service(e) => format!("{}\n{e}", t!("error-service")),
+        ErrorKind::Signal(e) => format!("{}\n{e}", t!("error-signal")),
+        ErrorKind::State(e) => format!("{}\n{e}", t!("error-state")),
+        ErrorKind::Terminal(e) => format!("{}\n{e}", t!("error-terminal")),
+        ErrorKind::Unknown(e) => format!("{}\n{e}", t!("error-unknown")),
+    }
+}
+
+/// Appends the reasons Cloudflare gave (e.g. `10000: Authentication error`) to the message
+fn with_api_messages(message: String, api_messages: &[ApiMessage]) -> String {
+    api_messages
+        .iter()
+        .fold(message, |message, x| match x.code {
+            Some(code) => format!("{message}\n{code}: {}", x.message),
+            None => format!("{message}\n{}", x.message),
+        })
 }
@@ -0,0 +1,361 @@
+use crate::{i18n::t, output};
+use std::time::Duration;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub(crate) use socket::listen;
+#[cfg(windows)]
+pub(crate) use windows::listen;
+
+/// Addresses often change in bursts (e.g. a new prefix on several interfaces)
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// Change of an address, the address is only known on Linux
+struct Change {
+    added: bool,
+    address: Option<std::net::IpAddr>,
+    index: u32,
+}
+
+fn log(change: &Change) {
+    let message = match (change.added, change.address) {
+        (true, Some(x)) => t!("event-address-added", address = x),
+        (false, Some(x)) => t!("event-address-removed", address = x),
+        (_, None) => t!("event-address-changed"),
+    };
+    output::info(message);
+}
+
+/// Reads the address changes from a netlink (Linux) or routing (macOS) socket
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod socket {
+    use super::{log, platform, Change, SETTLE_DELAY};
+    use crate::{daemon::Trigger, i18n::t, output};
+    use std::{
+        ffi::CString,
+        io::{Error as IOError, ErrorKind},
+        os::fd::{AsRawFd, OwnedFd},
+    };
+    use tokio::{
+        io::{unix::AsyncFd, Interest},
+        sync::mpsc::Sender,
+        time::sleep,
+    };
+
+    /// Triggers a check whenever one of the interfaces (all if empty) gains or loses an address
+    pub(crate) fn listen(interfaces: &[String], triggers: Sender<Trigger>) -> Result<(), IOError> {
+        let indexes = interfaces
+            .iter()
+            .map(|x| index(x))
+            .collect::<Result<Vec<_>, _>>()?;
+        let socket = AsyncFd::new(platform::open()?)?;
+
+        tokio::spawn(async move {
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                let length = match read(&socket, &mut buffer).await {
+                    Ok(x) => x,
+                    // The kernel drops the messages of a burst that doesn't fit into the buffer of
+                    // the socket, a check covers the lost changes
+                    Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                        output::info(t!("event-overflow"));
+                        if triggers.send(Trigger::Sync).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        output::error(t!("event-listener-failed", error = e));
+                        break;
+                    }
+                };
+
+                let changes = platform::parse(buffer.get(..length).unwrap_or_default())
+                    .into_iter()
+                    .filter(|x| indexes.is_empty() || indexes.contains(&x.index))
+                    .collect::<Vec<Change>>();
+                if changes.is_empty() {
+                    continue;
+                }
+                changes.iter().for_each(log);
+
+                sleep(SETTLE_DELAY).await;
+                // Discard the rest of the burst, it is covered by the same check
+                while socket
+                    .try_io(Interest::READABLE, |x| recv(x, &mut buffer))
+                    .is_ok()
+                {}
+
+                if triggers.send(Trigger::Sync).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn index(interface: &str) -> Result<u32, IOError> {
+        let name = CString::new(interface).map_err(IOError::other)?;
+        // SAFETY: the name is a valid NUL-terminated string
+        match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+            0 => Err(IOError::new(
+                ErrorKind::NotFound,
+                t!("event-interface-not-found", interface = interface),
+            )),
+            x => Ok(x),
+        }
+    }
+
+    async fn read(socket: &AsyncFd<OwnedFd>, buffer: &mut [u8]) -> Result<usize, IOError> {
+        loop {
+            let mut guard = socket.readable().await?;
+            if let Ok(result) = guard.try_io(|x| recv(x.get_ref(), buffer)) {
+                return result;
+            }
+        }
+    }
+
+    fn recv(fd: &OwnedFd, buffer: &mut [u8]) -> Result<usize, IOError> {
+        // SAFETY: the pointer and length describe the buffer
+        let length =
+            unsafe { libc::recv(fd.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len(), 0) };
+        usize::try_from(length).map_err(|_| IOError::last_os_error())
+    }
+
+    pub(super) fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+        let bytes = data.get(offset..offset + 2)?;
+        Some(u16::from_ne_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(super) fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{
+        socket::{u16_at, u32_at},
+        Change,
+    };
+    use std::{
+        io::Error as IOError,
+        mem,
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    };
+
+    const HEADER_LENGTH: usize = 16;
+    const RTM_NEWADDR: u16 = 20;
+    const RTM_DELADDR: u16 = 21;
+    const IFA_ADDRESS: u16 = 1;
+    const IFA_LOCAL: u16 = 2;
+    const RT_SCOPE_UNIVERSE: u8 = 0;
+
+    /// Opens a netlink socket subscribed to the address changes of IPv4 and IPv6
+    pub(super) fn open() -> Result<OwnedFd, IOError> {
+        // SAFETY: plain system call without pointers
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if fd < 0 {
+            return Err(IOError::last_os_error());
+        }
+        // SAFETY: the descriptor has just been created and is owned by nothing else
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: sockaddr_nl consists of integers only, for which zero is valid
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as u16;
+        address.nl_groups = (libc::RTMGRP_IPV4_IFADDR | libc::RTMGRP_IPV6_IFADDR) as u32;
+
+        // SAFETY: the pointer and length describe the address above
+        let result = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                (&address as *const libc::sockaddr_nl).cast(),
+                mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if result < 0 {
+            return Err(IOError::last_os_error());
+        }
+
+        Ok(fd)
+    }
+
+    /// Extracts the global addresses of RTM_NEWADDR and RTM_DELADDR messages
+    pub(super) fn parse(data: &[u8]) -> Vec<Change> {
+        let mut changes = Vec::new();
+        let mut offset = 0;
+
+        while let (Some(length), Some(type_)) = (u32_at(data, offset), u16_at(data, offset + 4)) {
+            let length = usize::try_from(length).unwrap_or_default();
+            let message = match data.get(offset + HEADER_LENGTH..offset + length) {
+                Some(x) if length >= HEADER_LENGTH => x,
+                _ => break,
+            };
+            offset += align(length);
+
+            if type_ != RTM_NEWADDR && type_ != RTM_DELADDR {
+                continue;
+            }
+
+            // ifaddrmsg: family, prefix length, flags, scope and interface index
+            let (scope, index) = match (message.get(3), u32_at(message, 4)) {
+                (Some(x), Some(y)) => (*x, y),
+                _ => continue,
+            };
+
+            let mut local = None;
+            let mut address = None;
+            let mut position = 8;
+            while let (Some(attribute_length), Some(attribute_type)) =
+                (u16_at(message, position), u16_at(message, position + 2))
+            {
+                let attribute_length = usize::from(attribute_length);
+                let value = match message.get(position + 4..position + attribute_length) {
+                    Some(x) if attribute_length >= 4 => x,
+                    _ => break,
+                };
+                position += align(attribute_length);
+
+                let ip = match value.len() {
+                    4 => <[u8; 4]>::try_from(value)
+                        .ok()
+                        .map(|x| IpAddr::V4(Ipv4Addr::from(x))),
+                    16 => <[u8; 16]>::try_from(value)
+                        .ok()
+                        .map(|x| IpAddr::V6(Ipv6Addr::from(x))),
+                    _ => None,
+                };
+                match attribute_type {
+                    IFA_ADDRESS => address = ip,
+                    IFA_LOCAL => local = ip,
+                    _ => {}
+                }
+            }
+
+            // IFA_ADDRESS is the address of the peer on point-to-point interfaces
+            if let Some(address) = local.or(address) {
+                if scope == RT_SCOPE_UNIVERSE && address.is_global() {
+                    changes.push(Change {
+                        added: type_ == RTM_NEWADDR,
+                        address: Some(address),
+                        index,
+                    });
+                }
+            }
+        }
+
+        changes
+    }
+
+    fn align(length: usize) -> usize {
+        (length + 3) & !3
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{socket::u16_at, Change};
+    use std::{
+        io::Error as IOError,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    };
+
+    const RTM_NEWADDR: u8 = 0xc;
+    const RTM_DELADDR: u8 = 0xd;
+    /// Offset of ifam_index in ifa_msghdr
+    const INDEX_OFFSET: usize = 12;
+
+    /// Opens a routing socket, which receives all changes of the routing table and addresses
+    pub(super) fn open() -> Result<OwnedFd, IOError> {
+        // SAFETY: plain system call without pointers
+        let fd = unsafe { libc::socket(libc::PF_ROUTE, libc::SOCK_RAW, libc::AF_UNSPEC) };
+        if fd < 0 {
+            return Err(IOError::last_os_error());
+        }
+        // SAFETY: the descriptor has just been created and is owned by nothing else
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: plain system calls without pointers on a valid descriptor
+        let result = unsafe {
+            let flags = libc::fcntl(fd.as_raw_fd(), libc::F_GETFL);
+            libc::fcntl(fd.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK)
+        };
+        if result < 0 {
+            return Err(IOError::last_os_error());
+        }
+
+        Ok(fd)
+    }
+
+    /// Extracts the interfaces of RTM_NEWADDR and RTM_DELADDR messages
+    pub(super) fn parse(data: &[u8]) -> Vec<Change> {
+        let mut changes = Vec::new();
+        let mut offset = 0;
+
+        while let (Some(length), Some(type_)) = (u16_at(data, offset), data.get(offset + 3)) {
+            let length = usize::from(length);
+            if length == 0 {
+                break;
+            }
+
+            if *type_ == RTM_NEWADDR || *type_ == RTM_DELADDR {
+                if let Some(index) = u16_at(data, offset + INDEX_OFFSET) {
+                    changes.push(Change {
+                        added: *type_ == RTM_NEWADDR,
+                        address: None,
+                        index: u32::from(index),
+                    });
+                }
+            }
+            offset += length;
+        }
+
+        changes
+    }
+}
+
+/// Waits for address changes with NotifyAddrChange, which does not tell the interface
+#[cfg(windows)]
+mod windows {
+    use super::{log, Change, SETTLE_DELAY};
+    use crate::{daemon::Trigger, i18n::t, output};
+    use std::{io::Error as IOError, ptr, thread};
+    use tokio::sync::mpsc::Sender;
+    use windows_sys::Win32::{Foundation::NO_ERROR, NetworkManagement::IpHelper::NotifyAddrChange};
+
+    pub(crate) fn listen(interfaces: &[String], triggers: Sender<Trigger>) -> Result<(), IOError> {
+        if !interfaces.is_empty() {
+            output::error(t!("event-interfaces-unsupported"));
+        }
+
+        // A detached thread, the runtime would wait for a blocking task until an address changes
+        thread::spawn(move || loop {
+            // SAFETY: without handle and overlapped structure the call blocks until an address changes
+            if unsafe { NotifyAddrChange(ptr::null_mut(), ptr::null()) } != NO_ERROR {
+                break;
+            }
+            log(&Change {
+                added: true,
+                address: None,
+                index: 0,
+            });
+
+            thread::sleep(SETTLE_DELAY);
+            if triggers.blocking_send(Trigger::Sync).is_err() {
+                break;
+            }
+        });
+
+        Ok(())
+    }
+}
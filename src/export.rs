@@ -0,0 +1,200 @@
+use crate::{
+    api::{
+        api_get, api_get_all, api_messages, client, deserialize_json_value, Auth, RECORDS_PER_PAGE,
+        ZONES_PER_PAGE,
+    },
+    config,
+    errors::{handle_errors, ErrorKind, Failure},
+    i18n::t,
+    output,
+    structs::{
+        cloudflare::response::{ListDnsRecords, ListZone},
+        config::Config,
+        ExportFormat,
+    },
+    sync::{obtain_records, obtain_zone},
+};
+use reqwest::{Client as HttpClient, Url};
+
+/// Cloudflare uses a TTL of 1 for "automatic", which currently means 300 seconds
+const AUTOMATIC_TTL: u32 = 300;
+
+pub(crate) async fn run(
+    config: &Config,
+    format: ExportFormat,
+    zone_export: bool,
+) -> Result<(), Failure> {
+    let http: HttpClient =
+        client(config).map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 134))?;
+
+    let api_base: Url = Url::parse("https://api.cloudflare.com/client/v4/")
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 117))?;
+
+    for account in config::accounts(config) {
+        let config = &account;
+
+        let mut data_zones = config::configured_zones(config);
+        if config::needs_zone_listing(config, &data_zones) {
+            let url_list_zones = api_base
+                .join("zones")
+                .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 118))?;
+
+            let json_zones = api_get_all(&http, url_list_zones, Auth::of(config), ZONES_PER_PAGE)
+                .await
+                .map_err(|_| Failure::new(ErrorKind::Api, 119))?
+                .map_err(|e| Failure::new(e, 120))?;
+
+            let listed = deserialize_json_value::<Vec<ListZone>>(json_zones.result)
+                .await
+                .map_err(|e| Failure::new(e, 121))?;
+            for zone in listed {
+                if !data_zones.iter().any(|x| x.name == zone.name) {
+                    data_zones.push(zone);
+                }
+            }
+        }
+
+        let zone_names = data_zones
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        let resolved = config::resolve_records(&config.records, &zone_names);
+
+        for (config_zone, targets) in &resolved {
+            let zone = match obtain_zone(&data_zones, config_zone).await {
+                Some(x) => x,
+                None => {
+                    output::error(t!("zone-not-found", zone = config_zone));
+                    continue;
+                }
+            };
+
+            let names = targets
+                .iter()
+                .map(|x| x.name.to_owned())
+                .collect::<Vec<_>>();
+
+            let lines = match zone_export {
+                true => zone_file_lines(&http, &api_base, config, &zone, &names).await?,
+                false => record_lines(&http, &api_base, config, &zone, &names).await?,
+            };
+
+            match format {
+                ExportFormat::Bind => {
+                    println!("$ORIGIN {}.", zone.name);
+                    for line in lines {
+                        println!("{line}");
+                    }
+                    println!();
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn record_lines(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    names: &[String],
+) -> Result<Vec<String>, Failure> {
+    let url_list_dns_records = api_base
+        .join(format!("zones/{}/dns_records", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 122))?;
+
+    let response_records = api_get_all(
+        http,
+        url_list_dns_records,
+        Auth::of(config),
+        RECORDS_PER_PAGE,
+    )
+    .await
+    .map_err(|_| Failure::new(ErrorKind::Api, 123))?;
+
+    let json_records = match response_records {
+        Ok(x) => x,
+        Err(e) => match e {
+            ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_) => {
+                handle_errors(&e);
+                return Ok(Vec::new());
+            }
+            _ => return Err(Failure::new(e, 124)),
+        },
+    };
+
+    let data_records = deserialize_json_value::<Vec<ListDnsRecords>>(json_records.result)
+        .await
+        .map_err(|e| Failure::new(e, 125))?;
+
+    let mut lines = Vec::new();
+    for name in names {
+        for record in obtain_records(&data_records, name).await {
+            let ttl = match record.ttl {
+                1 => AUTOMATIC_TTL,
+                x => x,
+            };
+            lines.push(format!(
+                "{}.\t{}\tIN\t{}\t{}",
+                record.name,
+                ttl,
+                record.type_.to_uppercase(),
+                record.content
+            ));
+        }
+    }
+    Ok(lines)
+}
+
+async fn zone_file_lines(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    names: &[String],
+) -> Result<Vec<String>, Failure> {
+    let url_export = api_base
+        .join(format!("zones/{}/dns_records/export", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 126))?;
+
+    let response_export = api_get(http, url_export, Auth::of(config))
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 127))?;
+
+    if !response_export.status().is_success() {
+        handle_errors(&ErrorKind::NoSuccessHttp(
+            api_messages(response_export).await,
+        ));
+        return Ok(Vec::new());
+    }
+
+    let text = response_export
+        .text()
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 128))?;
+
+    let owners = names
+        .iter()
+        .map(|x| format!("{}.", x.to_lowercase()))
+        .collect::<Vec<_>>();
+
+    let lines = text
+        .lines()
+        .filter(|line| {
+            let mut fields = line.split_whitespace();
+            let owner = match fields.next() {
+                Some(x) => x.to_lowercase(),
+                None => return false,
+            };
+            let type_ = fields
+                .find(|x| !x.chars().all(|c| c.is_ascii_digit()) && !x.eq_ignore_ascii_case("IN"));
+            owners.contains(&owner)
+                && type_
+                    .is_some_and(|x| x.eq_ignore_ascii_case("A") || x.eq_ignore_ascii_case("AAAA"))
+        })
+        .map(|line| line.to_owned())
+        .collect();
+    Ok(lines)
+}
@@ -0,0 +1,155 @@
+use crate::{i18n::t, output, structs::fixture::Fixture};
+use reqwest::{header, Error as HttpError, RequestBuilder, Response, Url};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Error as IOError,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+#[derive(Debug, Clone)]
+pub(crate) enum Mode {
+    /// Store all exchanges in this directory
+    Record(PathBuf),
+    /// Answer all requests from the exchanges stored in this directory instead of the network
+    Replay(PathBuf),
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+/// Number of requests per method and URL, repeated requests are stored as separate fixtures
+static COUNTERS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn init(mode: Mode) -> Result<(), IOError> {
+    if let Mode::Record(directory) = &mode {
+        fs::create_dir_all(directory)?;
+    }
+    let _ = MODE.set(mode);
+    Ok(())
+}
+
+/// Sends a request, or records or replays it if requested on the command line
+pub(crate) async fn send(request: RequestBuilder) -> Result<Response, HttpError> {
+    let mode = match MODE.get() {
+        Some(x) => x,
+        None => return request.send().await,
+    };
+
+    let (client, request) = request.build_split();
+    let request = request?;
+    let method = request.method().to_string();
+    let url = request.url().to_owned();
+
+    match mode {
+        Mode::Replay(directory) => {
+            let path = fixture_path(directory, &method, &url);
+            let fixture = fs::read_to_string(&path)
+                .ok()
+                .and_then(|x| serde_json::from_str::<Fixture>(&x).ok());
+
+            Ok(match fixture {
+                Some(x) => {
+                    let body = match (x.body, x.body_hex) {
+                        (Some(body), _) => body.into_bytes(),
+                        (None, Some(hex)) => decode_hex(&hex),
+                        (None, None) => Vec::new(),
+                    };
+                    response(x.status, x.content_type.as_deref(), body)
+                }
+                None => {
+                    output::error(t!(
+                        "fixture-missing",
+                        method = method,
+                        url = redact(&url),
+                        path = path.display()
+                    ));
+                    response(504, None, Vec::new())
+                }
+            })
+        }
+        Mode::Record(directory) => {
+            let path = fixture_path(directory, &method, &url);
+            let request_body = request
+                .body()
+                .and_then(|x| x.as_bytes())
+                .map(|x| String::from_utf8_lossy(x).into_owned());
+
+            let response_original = client.execute(request).await?;
+            let status = response_original.status().as_u16();
+            let content_type = response_original
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|x| x.to_str().ok())
+                .map(String::from);
+            let body = response_original.bytes().await?.to_vec();
+
+            let (body_text, body_hex) = match String::from_utf8(body.to_owned()) {
+                Ok(x) => (Some(x), None),
+                Err(_) => (None, Some(encode_hex(&body))),
+            };
+            let fixture = Fixture {
+                body: body_text,
+                body_hex,
+                content_type: content_type.to_owned(),
+                method,
+                request_body,
+                status,
+                url: redact(&url),
+            };
+
+            let result = serde_json::to_string_pretty(&fixture)
+                .map_err(IOError::other)
+                .and_then(|x| fs::write(&path, x));
+            if let Err(e) = result {
+                output::error(format!(
+                    "{}\n{e}",
+                    t!("fixture-failed", path = path.display())
+                ));
+            }
+
+            Ok(response(status, content_type.as_deref(), body))
+        }
+    }
+}
+
+/// Fixtures are named after a hash of the request, which keeps secrets in the URL out of the file name
+fn fixture_path(directory: &Path, method: &str, url: &Url) -> PathBuf {
+    let key = format!("{method} {url}");
+    let count = {
+        let mut counters = COUNTERS.lock().unwrap_or_else(|e| e.into_inner());
+        let count = counters.entry(key.to_owned()).or_default();
+        *count += 1;
+        *count
+    };
+    let hash = encode_hex(&Sha256::digest(key.as_bytes())[..8]);
+    directory.join(format!("{hash}-{count}.json"))
+}
+
+fn redact(url: &Url) -> String {
+    let mut url = url.to_owned();
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.to_string()
+}
+
+fn response(status: u16, content_type: Option<&str>, body: Vec<u8>) -> Response {
+    let mut builder = http::Response::builder().status(status);
+    if let Some(x) = content_type {
+        builder = builder.header(header::CONTENT_TYPE, x);
+    }
+    match builder.body(body) {
+        Ok(x) => Response::from(x),
+        Err(_) => Response::from(http::Response::new(Vec::new())),
+    }
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|x| format!("{x:02x}")).collect()
+}
+
+fn decode_hex(data: &str) -> Vec<u8> {
+    (0..data.len() / 2)
+        .filter_map(|i| u8::from_str_radix(data.get(i * 2..i * 2 + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,407 @@
+use crate::{
+    fixtures, ip,
+    structs::config::{FritzboxSource, MikrotikSource, UbusSource, Uplink},
+};
+use md5::{Digest, Md5};
+use reqwest::{header, Client as HttpClient, StatusCode, Url};
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    str::FromStr,
+    time::Duration,
+};
+use tokio::{net::UdpSocket, process::Command, time::timeout};
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+const PCP_PORT: u16 = 5351;
+const PCP_VERSION: u8 = 2;
+const PCP_OPCODE_MAP: u8 = 1;
+/// Seconds of the mapping that is requested to learn the external address
+const PCP_LIFETIME: u32 = 60;
+const SSDP_ADDRESS: SocketAddr = SocketAddr::new(
+    std::net::IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)),
+    1900,
+);
+const SSDP_SEARCH_TARGET: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+/// Services of an Internet Gateway Device that provide GetExternalIPAddress
+const UPNP_SERVICES: [&str; 2] = ["WANIPConnection", "WANPPPConnection"];
+/// Service types and control URLs of the WAN connections in TR-064
+const FRITZBOX_SERVICES: [(&str, &str); 2] = [
+    (
+        "urn:dslforum-org:service:WANIPConnection:1",
+        "/upnp/control/wanipconnection1",
+    ),
+    (
+        "urn:dslforum-org:service:WANPPPConnection:1",
+        "/upnp/control/wanpppconn1",
+    ),
+];
+
+/// Asks the Internet Gateway Device of the local network for its external address (UPnP)
+pub(crate) async fn upnp(local_address: Option<Ipv4Addr>) -> Option<Ipv4Addr> {
+    let location = discover(local_address.unwrap_or(Ipv4Addr::UNSPECIFIED)).await?;
+    let location = Url::parse(&location).ok()?;
+
+    // The gateway is part of the local network, so neither proxies nor HTTP/3 apply
+    let http = HttpClient::builder()
+        .no_proxy()
+        .timeout(TIMEOUT)
+        .build()
+        .ok()?;
+
+    let description = fixtures::send(http.get(location.to_owned()))
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let (service_type, control_url) = find_service(&description)?;
+
+    let url = location.join(control_url).ok()?;
+    let response = soap(&http, url, service_type, "GetExternalIPAddress", None).await?;
+    Ipv4Addr::from_str(element(&response, "NewExternalIPAddress")?.trim()).ok()
+}
+
+/// Asks a Fritz!Box for the addresses of its WAN connection via TR-064, which also works behind
+/// another NAT
+pub(crate) async fn fritzbox(source: &FritzboxSource, ip_enum: IpAddr) -> Option<IpAddr> {
+    let http = HttpClient::builder()
+        .no_proxy()
+        .timeout(TIMEOUT)
+        .build()
+        .ok()?;
+    let credentials = source
+        .username
+        .as_deref()
+        .map(|x| (x, source.password.as_deref().unwrap_or_default()));
+
+    // Cable and fiber connections use IP, DSL connections usually PPP
+    for (service_type, path) in FRITZBOX_SERVICES {
+        let url = Url::parse(&format!("http://{}:{}{path}", source.host, source.port)).ok()?;
+        let (action, name) = match ip_enum {
+            IpAddr::V4(_) => ("GetExternalIPAddress", "NewExternalIPAddress"),
+            IpAddr::V6(_) => ("X_AVM_DE_GetExternalIPv6Address", "NewExternalIPv6Address"),
+        };
+
+        let address = soap(&http, url, service_type, action, credentials)
+            .await
+            .and_then(|x| IpAddr::from_str(element(&x, name)?.trim()).ok())
+            .filter(|x| !x.is_unspecified());
+        if address.is_some() {
+            return address;
+        }
+    }
+
+    None
+}
+
+/// Reads the address of an interface or the first entry of an address list of a MikroTik router
+/// (RouterOS 7 REST API)
+pub(crate) async fn mikrotik(source: &MikrotikSource, ip_enum: IpAddr) -> Option<IpAddr> {
+    let http = HttpClient::builder()
+        .danger_accept_invalid_certs(source.insecure)
+        .timeout(TIMEOUT)
+        .build()
+        .ok()?;
+
+    let family = match ip_enum {
+        IpAddr::V4(_) => "ip",
+        IpAddr::V6(_) => "ipv6",
+    };
+    let (path, filter) = match (&source.address_list, &source.interface) {
+        (Some(x), _) => ("firewall/address-list", ("list", x)),
+        (None, Some(x)) => ("address", ("interface", x)),
+        (None, None) => return None,
+    };
+    let mut url = Url::parse(&source.url)
+        .ok()?
+        .join(&format!("rest/{family}/{path}"))
+        .ok()?;
+    url.query_pairs_mut().append_pair(filter.0, filter.1);
+
+    let request = http
+        .get(url)
+        .basic_auth(&source.username, source.password.as_ref());
+    let entries = fixtures::send(request)
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .json::<Vec<HashMap<String, serde_json::Value>>>()
+        .await
+        .ok()?;
+
+    entries
+        .iter()
+        .filter(|x| {
+            ["disabled", "invalid"]
+                .iter()
+                .all(|key| x.get(*key).and_then(|x| x.as_str()) != Some("true"))
+        })
+        .filter_map(|x| {
+            // Interface addresses contain the prefix length
+            let address = x.get("address")?.as_str()?;
+            let address = address.split('/').next().unwrap_or(address);
+            IpAddr::from_str(address).ok()
+        })
+        .find(|x| x.is_ipv4() == ip_enum.is_ipv4() && (x.is_ipv4() || x.is_global()))
+}
+
+/// Asks ubus for the status of the interfaces, for IPv6 the delegated prefix is used if the
+/// interface itself has no global address
+pub(crate) async fn ubus(source: &UbusSource, ip_enum: IpAddr) -> Option<IpAddr> {
+    let keys: &[&str] = match ip_enum {
+        IpAddr::V4(_) => &["ipv4-address"],
+        IpAddr::V6(_) => &["ipv6-address", "ipv6-prefix"],
+    };
+
+    for interface in &source.interfaces {
+        let output = Command::new("ubus")
+            .args(["call", &format!("network.interface.{interface}"), "status"])
+            .output();
+        let status = match timeout(TIMEOUT, output).await {
+            Ok(Ok(x)) if x.status.success() => x.stdout,
+            _ => continue,
+        };
+        let status = match serde_json::from_slice::<serde_json::Value>(&status) {
+            Ok(x) => x,
+            Err(_) => continue,
+        };
+
+        let address = keys.iter().find_map(|key| {
+            status
+                .get(key)?
+                .as_array()?
+                .iter()
+                .filter_map(|x| IpAddr::from_str(x.get("address")?.as_str()?).ok())
+                .find(|x| x.is_ipv4() == ip_enum.is_ipv4() && (x.is_ipv4() || x.is_global()))
+        });
+        if address.is_some() {
+            return address;
+        }
+    }
+
+    None
+}
+
+/// Calls an action without arguments, answering a digest challenge (RFC 2617) with the
+/// credentials if there are any
+async fn soap(
+    http: &HttpClient,
+    url: Url,
+    service_type: &str,
+    action: &str,
+    credentials: Option<(&str, &str)>,
+) -> Option<String> {
+    let body = format!(
+        r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body><u:{action} xmlns:u="{service_type}"/></s:Body>
+</s:Envelope>"#
+    );
+    let request = |authorization: Option<String>| {
+        let request = http
+            .post(url.to_owned())
+            .header(header::CONTENT_TYPE, "text/xml; charset=\"utf-8\"")
+            .header("SOAPAction", format!("\"{service_type}#{action}\""))
+            .body(body.to_owned());
+        match authorization {
+            Some(x) => request.header(header::AUTHORIZATION, x),
+            None => request,
+        }
+    };
+
+    let mut response = fixtures::send(request(None)).await.ok()?;
+    if let (StatusCode::UNAUTHORIZED, Some((username, password))) = (response.status(), credentials)
+    {
+        let challenge = response
+            .headers()
+            .get(header::WWW_AUTHENTICATE)?
+            .to_str()
+            .ok()?
+            .to_owned();
+        let authorization = digest(&challenge, username, password, url.path())?;
+        response = fixtures::send(request(Some(authorization))).await.ok()?;
+    }
+
+    response.error_for_status().ok()?.text().await.ok()
+}
+
+fn digest(challenge: &str, username: &str, password: &str, uri: &str) -> Option<String> {
+    let parameters = challenge
+        .strip_prefix("Digest ")?
+        .split(',')
+        .filter_map(|x| {
+            let (key, value) = x.trim().split_once('=')?;
+            Some((key.to_lowercase(), value.trim_matches('"').to_owned()))
+        })
+        .collect::<HashMap<_, _>>();
+    let realm = parameters.get("realm")?;
+    let nonce = parameters.get("nonce")?;
+
+    let md5 = |x: String| hex(&Md5::digest(x.as_bytes()));
+    let ha1 = md5(format!("{username}:{realm}:{password}"));
+    let ha2 = md5(format!("POST:{uri}"));
+
+    let mut authorization = format!(
+        r#"Digest username="{username}", realm="{realm}", nonce="{nonce}", uri="{uri}", algorithm=MD5"#
+    );
+    let response = match parameters.get("qop") {
+        Some(qop) if qop.split(',').any(|x| x.trim() == "auth") => {
+            let cnonce = format!("{:016x}", RandomState::new().build_hasher().finish());
+            authorization.push_str(&format!(r#", qop=auth, nc=00000001, cnonce="{cnonce}""#));
+            md5(format!("{ha1}:{nonce}:00000001:{cnonce}:auth:{ha2}"))
+        }
+        _ => md5(format!("{ha1}:{nonce}:{ha2}")),
+    };
+    authorization.push_str(&format!(r#", response="{response}""#));
+    if let Some(opaque) = parameters.get("opaque") {
+        authorization.push_str(&format!(r#", opaque="{opaque}""#));
+    }
+    Some(authorization)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{x:02x}")).collect()
+}
+
+/// Searches the gateway with SSDP and returns the location of its device description
+async fn discover(local_address: Ipv4Addr) -> Option<String> {
+    let socket = UdpSocket::bind(SocketAddr::from((local_address, 0)))
+        .await
+        .ok()?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {SSDP_ADDRESS}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {SSDP_SEARCH_TARGET}\r\n\r\n"
+    );
+    socket
+        .send_to(request.as_bytes(), SSDP_ADDRESS)
+        .await
+        .ok()?;
+
+    let mut buffer = vec![0; 2048];
+    let receive = async {
+        loop {
+            let (length, _) = socket.recv_from(&mut buffer).await.ok()?;
+            let response = String::from_utf8_lossy(buffer.get(..length).unwrap_or_default());
+            let location = response.lines().find_map(|x| {
+                let (name, value) = x.split_once(':')?;
+                name.trim()
+                    .eq_ignore_ascii_case("location")
+                    .then(|| value.trim().to_owned())
+            });
+            if location.is_some() {
+                return location;
+            }
+        }
+    };
+
+    timeout(TIMEOUT, receive).await.ok()?
+}
+
+/// Service type and control URL of the first WAN connection service in the device description
+fn find_service(description: &str) -> Option<(&str, &str)> {
+    description.split("<service>").skip(1).find_map(|service| {
+        let service_type = element(service, "serviceType")?.trim();
+        let control_url = element(service, "controlURL")?.trim();
+        UPNP_SERVICES
+            .iter()
+            .any(|x| service_type.contains(x))
+            .then_some((service_type, control_url))
+    })
+}
+
+/// Content of the first element with the name, regardless of its namespace prefix
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = xml;
+    loop {
+        let start = rest.find('<')?;
+        rest = rest.get(start + 1..)?;
+        let (tag, content) = rest.split_once('>')?;
+        let tag = tag.split_whitespace().next().unwrap_or_default();
+        let local_name = tag.rsplit(':').next().unwrap_or(tag);
+        if local_name == name {
+            return content.split('<').next();
+        }
+    }
+}
+
+/// Asks the gateway for its external address with NAT-PMP (RFC 6886), falls back to PCP
+/// (RFC 6887) if the gateway only supports the latter
+pub(crate) async fn natpmp(gateway: Option<Ipv4Addr>, uplink: Option<&Uplink>) -> Option<Ipv4Addr> {
+    let gateway = SocketAddr::from((gateway.or_else(default_gateway)?, PCP_PORT));
+    let socket = ip::udp_socket(gateway, uplink).await.ok()?;
+
+    let response = ip::exchange_udp(&socket, &[0, 0], |x| {
+        // PCP servers answer NAT-PMP requests with their own version
+        x.get(1) == Some(&128) || x.first() == Some(&PCP_VERSION)
+    })
+    .await?;
+
+    match response.as_slice() {
+        [0, 128, 0, 0, _, _, _, _, a, b, c, d, ..] => Some(Ipv4Addr::new(*a, *b, *c, *d)),
+        [PCP_VERSION, ..] | [0, 128, 0, 1, ..] => pcp(&socket).await,
+        _ => None,
+    }
+}
+
+/// Requests a short-lived mapping, whose response contains the external address, and deletes it
+async fn pcp(socket: &UdpSocket) -> Option<Ipv4Addr> {
+    let local_address = match socket.local_addr().ok()? {
+        SocketAddr::V4(x) => *x.ip(),
+        SocketAddr::V6(_) => return None,
+    };
+    let port = socket.local_addr().ok()?.port();
+
+    let random = RandomState::new().build_hasher().finish().to_be_bytes();
+    let nonce = [random.as_slice(), &random[..4]].concat();
+
+    let request = |lifetime: u32| {
+        let mut request = Vec::with_capacity(60);
+        request.extend_from_slice(&[PCP_VERSION, PCP_OPCODE_MAP, 0, 0]);
+        request.extend_from_slice(&lifetime.to_be_bytes());
+        request.extend_from_slice(&local_address.to_ipv6_mapped().octets());
+        request.extend_from_slice(&nonce);
+        // UDP, the port of the socket is as good as any other
+        request.extend_from_slice(&[17, 0, 0, 0]);
+        request.extend_from_slice(&port.to_be_bytes());
+        request.extend_from_slice(&port.to_be_bytes());
+        request.extend_from_slice(&Ipv4Addr::UNSPECIFIED.to_ipv6_mapped().octets());
+        request
+    };
+    let is_response = |x: &[u8]| {
+        x.get(..2) == Some(&[PCP_VERSION, PCP_OPCODE_MAP | 0x80]) && x.get(24..36) == Some(&nonce)
+    };
+
+    let response = ip::exchange_udp(socket, &request(PCP_LIFETIME), is_response).await?;
+    // Result code
+    if response.get(3) != Some(&0) {
+        return None;
+    }
+    let address = <[u8; 16]>::try_from(response.get(44..60)?).ok()?;
+    let _ = ip::exchange_udp(socket, &request(0), is_response).await;
+
+    std::net::Ipv6Addr::from(address).to_ipv4_mapped()
+}
+
+/// Gateway of the default route, read from the routing table of the kernel
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Option<Ipv4Addr> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+    routes.lines().skip(1).find_map(|x| {
+        let columns = x.split_whitespace().collect::<Vec<_>>();
+        match columns.as_slice() {
+            [_, "00000000", gateway, ..] => u32::from_str_radix(gateway, 16)
+                .ok()
+                .map(|x| Ipv4Addr::from(x.swap_bytes())),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Option<Ipv4Addr> {
+    None
+}
@@ -0,0 +1,103 @@
+use crate::{
+    i18n::t,
+    output,
+    structs::config::{Config, Geoip},
+};
+use maxminddb::Reader;
+use serde::Deserialize;
+use std::net::IpAddr;
+
+/// Fields of the ASN and country databases of MaxMind, DB-IP and IPinfo
+#[derive(Default, Debug, Deserialize)]
+#[serde(default)]
+struct Network {
+    autonomous_system_number: Option<u32>,
+    autonomous_system_organization: Option<String>,
+    country: Option<Country>,
+}
+
+#[derive(Default, Debug, Deserialize)]
+#[serde(default)]
+struct Country {
+    iso_code: Option<String>,
+}
+
+/// Logs the network of a detected address and whether it is expected, returns false if the address
+/// must not be published
+pub(crate) fn check(config: &Config, ip: IpAddr) -> bool {
+    let settings = match &config.detection.geoip {
+        Some(x) => x,
+        None => return true,
+    };
+
+    let network = lookup(settings, ip);
+    let asn = network.autonomous_system_number;
+    let country = network.country.and_then(|x| x.iso_code);
+
+    let description = match (asn, &network.autonomous_system_organization) {
+        (Some(asn), Some(organization)) => format!("AS{asn} {organization}"),
+        (Some(asn), None) => format!("AS{asn}"),
+        (None, _) => t!("geoip-unknown"),
+    };
+    let country_code = country.to_owned().unwrap_or_else(|| t!("geoip-unknown"));
+    output::info(t!(
+        "geoip-network",
+        ip = ip,
+        network = description,
+        country = country_code
+    ));
+
+    // Without data the network is not known to be unexpected
+    let is_expected = (settings.asns.is_empty() || asn.is_none_or(|x| settings.asns.contains(&x)))
+        && (settings.countries.is_empty()
+            || country.is_none_or(|x| {
+                settings
+                    .countries
+                    .iter()
+                    .any(|y| y.eq_ignore_ascii_case(&x))
+            }));
+    if is_expected {
+        return true;
+    }
+
+    output::error(t!(
+        "geoip-unexpected",
+        ip = ip,
+        network = description,
+        country = country_code
+    ));
+    if settings.reject {
+        output::error(t!("geoip-rejected", ip = ip));
+        return false;
+    }
+    true
+}
+
+/// Combines the fields of all databases, the first one containing a field wins
+fn lookup(settings: &Geoip, ip: IpAddr) -> Network {
+    let mut network = Network::default();
+    for path in &settings.databases {
+        let found =
+            Reader::open_readfile(path).and_then(|reader| reader.lookup(ip)?.decode::<Network>());
+        let found = match found {
+            Ok(Some(x)) => x,
+            Ok(None) => continue,
+            Err(e) => {
+                output::error(t!(
+                    "geoip-database",
+                    path = path.display(),
+                    error = e.to_string()
+                ));
+                continue;
+            }
+        };
+        network.autonomous_system_number = network
+            .autonomous_system_number
+            .or(found.autonomous_system_number);
+        network.autonomous_system_organization = network
+            .autonomous_system_organization
+            .or(found.autonomous_system_organization);
+        network.country = network.country.or(found.country);
+    }
+    network
+}
@@ -0,0 +1,114 @@
+use crate::{
+    api::{
+        api_get_all, api_post, api_put, deserialize_json_value, deserialize_response, Auth,
+        RECORDS_PER_PAGE,
+    },
+    errors::{ErrorKind, Failure},
+    i18n::t,
+    output,
+    structs::{
+        cloudflare::request::TxtRecord,
+        cloudflare::response::{ListDnsRecords, ListZone},
+        config::{Config, HighAvailability},
+    },
+};
+use chrono::Utc;
+use reqwest::{Client as HttpClient, Url};
+use std::{env, fs};
+
+/// Elects a leader among hosts sharing a configuration via the heartbeat in a TXT record,
+/// only the leader refreshes the heartbeat and publishes updates
+pub(crate) async fn is_leader(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    settings: &HighAvailability,
+    zones: &[ListZone],
+) -> Result<bool, Failure> {
+    let node = match settings.node.to_owned().or_else(hostname) {
+        Some(x) => x,
+        None => {
+            output::error(t!("ha-no-node"));
+            return Ok(false);
+        }
+    };
+
+    // The zone of the heartbeat record is the longest zone that contains it
+    let zone = zones
+        .iter()
+        .filter(|x| settings.record == x.name || settings.record.ends_with(&format!(".{}", x.name)))
+        .max_by_key(|x| x.name.len());
+    let zone = match zone {
+        Some(x) => x,
+        None => {
+            output::error(t!("zone-not-found", zone = settings.record));
+            return Ok(false);
+        }
+    };
+
+    let url_records = api_base
+        .join(format!("zones/{}/dns_records", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 147))?;
+    let mut url_list = url_records.to_owned();
+    url_list
+        .query_pairs_mut()
+        .append_pair("type", "TXT")
+        .append_pair("name", &settings.record);
+
+    let json = api_get_all(http, url_list, Auth::of(config), RECORDS_PER_PAGE)
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 148))?
+        .map_err(|e| Failure::new(e, 149))?;
+    let records = deserialize_json_value::<Vec<ListDnsRecords>>(json.result)
+        .await
+        .map_err(|e| Failure::new(e, 149))?;
+    let record = records.into_iter().find(|x| x.name == settings.record);
+
+    let now = Utc::now().timestamp();
+    if let Some(record) = &record {
+        // The content has the format "<node> <unix timestamp of the heartbeat>"
+        let content = record.content.trim_matches('"');
+        let (leader, heartbeat) = content.split_once(' ').unwrap_or((content, "0"));
+        let age = now.saturating_sub(heartbeat.parse::<i64>().unwrap_or_default());
+
+        if leader != node {
+            if age < i64::try_from(settings.timeout).unwrap_or(i64::MAX) {
+                output::info(t!("ha-standby", leader = leader, age = age));
+                return Ok(false);
+            }
+            output::notice(t!("ha-takeover", leader = leader, age = age));
+        }
+    }
+
+    let heartbeat = TxtRecord {
+        content: format!("\"{node} {now}\""),
+        name: settings.record.to_owned(),
+        ttl: 60,
+        type_: String::from("TXT"),
+    };
+    let response = match &record {
+        Some(x) => {
+            let url_record = api_base
+                .join(format!("zones/{}/dns_records/{}", zone.id, x.id).as_str())
+                .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 147))?;
+            api_put(http, url_record, Auth::of(config), &heartbeat).await
+        }
+        None => api_post(http, url_records, Auth::of(config), &heartbeat).await,
+    }
+    .map_err(|_| Failure::new(ErrorKind::Api, 148))?;
+    deserialize_response(response)
+        .await
+        .map_err(|e| Failure::new(e, 149))?;
+
+    Ok(true)
+}
+
+/// Name of this host, also used in the comments of updated records
+pub(crate) fn hostname() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| env::var("COMPUTERNAME").ok())
+        .or_else(|| env::var("HOSTNAME").ok())
+        .map(|x| x.trim().to_owned())
+        .filter(|x| !x.is_empty())
+}
@@ -0,0 +1,82 @@
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use std::{env, sync::OnceLock};
+use unic_langid::{langid, LanguageIdentifier};
+
+const ENGLISH: &str = include_str!("../locales/en.ftl");
+const GERMAN: &str = include_str!("../locales/de.ftl");
+
+struct Catalog {
+    bundle: FluentBundle<FluentResource>,
+    /// Messages missing from the selected language are taken from English
+    fallback: FluentBundle<FluentResource>,
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Looks up a message of the catalog, the arguments replace the `{ $name }` placeables
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::text($id, &[])
+    };
+    ($id:expr, $($key:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::text($id, &[$((stringify!($key), $value.to_string())),+])
+    };
+}
+
+pub(crate) use t;
+
+pub(crate) fn text(id: &str, args: &[(&str, String)]) -> String {
+    let catalog = CATALOG.get_or_init(|| Catalog {
+        bundle: bundle(language()),
+        fallback: bundle(langid!("en")),
+    });
+
+    let mut fluent_args = FluentArgs::new();
+    for (key, value) in args {
+        fluent_args.set(*key, value.to_owned());
+    }
+
+    for bundle in [&catalog.bundle, &catalog.fallback] {
+        let pattern = match bundle.get_message(id).and_then(|x| x.value()) {
+            Some(x) => x,
+            None => continue,
+        };
+        let mut errors = Vec::new();
+        return bundle
+            .format_pattern(pattern, Some(&fluent_args), &mut errors)
+            .into_owned();
+    }
+
+    id.to_owned()
+}
+
+/// Determines the language from the locale environment variables (in the order of POSIX precedence)
+fn language() -> LanguageIdentifier {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|x| env::var(x).ok())
+        .find(|x| !x.is_empty())
+        .and_then(|x| {
+            // e.g. "de_DE.UTF-8" or "de_AT@euro"
+            let language = x.split(['_', '.', '@']).next().unwrap_or_default();
+            language.parse::<LanguageIdentifier>().ok()
+        })
+        .unwrap_or(langid!("en"))
+}
+
+fn bundle(language: LanguageIdentifier) -> FluentBundle<FluentResource> {
+    let source = match language.language.as_str() {
+        "de" => GERMAN,
+        _ => ENGLISH,
+    };
+    let resource = match FluentResource::try_new(source.to_owned()) {
+        Ok(x) => x,
+        Err((x, _)) => x,
+    };
+
+    let mut bundle = FluentBundle::new_concurrent(vec![language]);
+    // Unicode isolation marks would end up in logs and terminals
+    bundle.set_use_isolating(false);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
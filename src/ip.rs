@@ -1,44 +1,645 @@
-use crate::structs::config::Config;
+use crate::{
+    api,
+    dns::{self, RecordType},
+    fixtures, gateway,
+    i18n::t,
+    output,
+    structs::config::{Config, DnsSource, Family, HttpSource, Npt, ResponseFormat, Source, Uplink},
+};
 use local_ip_address::list_afinet_netifas;
 use mac_address::get_mac_address;
-use reqwest::Client as HttpClient;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
 use std::{
+    collections::hash_map::RandomState,
     collections::HashMap,
-    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    future::Future,
+    hash::{BuildHasher, Hasher},
+    io::{Error as IOError, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+    process::Stdio,
     str::FromStr,
+    sync::{Arc, OnceLock},
     time::Duration,
 };
+use tokio::{
+    fs,
+    net::{lookup_host, UdpSocket},
+    process::Command,
+    sync::watch,
+    time::timeout,
+};
+
+pub(crate) const IPV4_UNSPECIFIED: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+pub(crate) const IPV6_UNSPECIFIED: IpAddr = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+const DEFAULT_SOURCE_URL: &str = "https://cloudflare.com/cdn-cgi/trace";
+const TRACE_URL_IPV4: &str = "https://1.1.1.1/cdn-cgi/trace";
+const TRACE_URL_IPV6: &str = "https://[2606:4700:4700::1111]/cdn-cgi/trace";
+const OPENDNS_NAME: &str = "myip.opendns.com";
+/// resolver1.opendns.com
+const OPENDNS_IPV4: IpAddr = IpAddr::V4(Ipv4Addr::new(208, 67, 222, 222));
+const OPENDNS_IPV6: IpAddr = IpAddr::V6(Ipv6Addr::new(0x2620, 0x119, 0x35, 0, 0, 0, 0, 0x35));
+const GOOGLE_NAME: &str = "o-o.myaddr.l.google.com";
+/// ns1.google.com, the name has to be asked of the authoritative server to get the own address
+const GOOGLE_IPV4: IpAddr = IpAddr::V4(Ipv4Addr::new(216, 239, 32, 10));
+const GOOGLE_IPV6: IpAddr = IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4802, 0x32, 0, 0, 0, 0xa));
+const STUN_MAGIC_COOKIE: u32 = 0x2112_a442;
+const STUN_BINDING_REQUEST: u16 = 0x0001;
+const STUN_BINDING_RESPONSE: u16 = 0x0101;
+const STUN_MAPPED_ADDRESS: u16 = 0x0001;
+const STUN_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+/// Requests over UDP are sent again if there is no response within this time
+const UDP_RETRANSMISSION: Duration = Duration::from_secs(2);
+const UDP_ATTEMPTS: usize = 3;
+
+/// Determines both families concurrently, so a hanging IPv6 source does not delay IPv4
+pub(crate) async fn determine_ip(
+    config: &Config,
+    family: Family,
+) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
+    let ipv4 = async {
+        match family.has_ipv4() {
+            true => determine_ipv4(config).await,
+            false => None,
+        }
+    };
+    let ipv6 = async {
+        match family.has_ipv6() {
+            true => determine_ipv6(config).await,
+            false => None,
+        }
+    };
+    tokio::join!(within_budget(config, ipv4), within_budget(config, ipv6))
+}
+
+/// Limits the detection of a family to the total timeout of the detection section
+async fn within_budget<T>(
+    config: &Config,
+    detection: impl Future<Output = Option<T>>,
+) -> Option<T> {
+    let seconds = match config.detection.total_timeout {
+        Some(x) => x,
+        None => return detection.await,
+    };
+    match timeout(Duration::from_secs(seconds), detection).await {
+        Ok(x) => x,
+        Err(_) => {
+            output::error(t!("detection-budget", seconds = seconds));
+            None
+        }
+    }
+}
+
+/// The detected address and whether it is the outgoing one seen from the outside (see
+/// [`Source::is_outgoing`])
+pub(crate) async fn query_ip(config: &Config, ip_enum: IpAddr) -> Option<(IpAddr, bool)> {
+    query_sources(config, &sources(config), ip_enum, None).await
+}
+
+/// Queries the sources in order until one of them returns an address, or all of them if a
+/// consensus is required. An address agreed on by a consensus is only the outgoing one if all of
+/// its sources see it from the outside
+async fn query_sources(
+    config: &Config,
+    sources: &[Source],
+    ip_enum: IpAddr,
+    uplink: Option<&Uplink>,
+) -> Option<(IpAddr, bool)> {
+    let required = match config.detection.consensus {
+        Some(x) if x > 1 => x,
+        _ => {
+            for (i, source) in sources.iter().enumerate() {
+                if let Some(x) = query_source(config, source, ip_enum, uplink).await {
+                    // Only mentioned if the preferred sources failed
+                    if i > 0 {
+                        output::info(t!("detection-fallback", ip = x, source = source.label()));
+                    }
+                    return Some((x, source.is_outgoing()));
+                }
+            }
+            return None;
+        }
+    };
+
+    let mut votes: Vec<(IpAddr, usize, bool)> = Vec::new();
+    for source in sources {
+        let Some(ip) = query_source(config, source, ip_enum, uplink).await else {
+            continue;
+        };
+        match votes.iter_mut().find(|(x, _, _)| *x == ip) {
+            Some((_, count, is_outgoing)) => {
+                *count += 1;
+                *is_outgoing &= source.is_outgoing();
+            }
+            None => votes.push((ip, 1, source.is_outgoing())),
+        }
+    }
+    votes.sort_by_key(|(_, count, _)| std::cmp::Reverse(*count));
+
+    match votes.first() {
+        Some((ip, count, is_outgoing)) if *count >= required => Some((*ip, *is_outgoing)),
+        _ => {
+            let answers = votes
+                .iter()
+                .map(|(ip, count, _)| format!("{ip} ({count})"))
+                .collect::<Vec<_>>();
+            output::error(t!(
+                "detection-no-consensus",
+                required = required,
+                answers = answers.join(", ")
+            ));
+            None
+        }
+    }
+}
+
+/// Warns about addresses of the shared address space (RFC 6598) of a carrier-grade NAT, they are
+/// not reachable from the internet. Returns whether the address should be skipped
+pub(crate) fn skip_cgnat(config: &Config, ipv4: Ipv4Addr) -> bool {
+    if !ipv4.is_shared() {
+        return false;
+    }
+
+    output::error(t!("cgnat-detected", ip = ipv4));
+    if config.detection.skip_on_cgnat {
+        output::info(t!("cgnat-skipped"));
+    }
+    config.detection.skip_on_cgnat
+}
+
+/// The configured detection sources or the default one if none are configured
+pub(crate) fn sources(config: &Config) -> Vec<Source> {
+    match config.detection.sources.is_empty() {
+        true => vec![Source::Http(HttpSource {
+            basic_auth: None,
+            format: ResponseFormat::Text,
+            headers: HashMap::new(),
+            url: String::from(DEFAULT_SOURCE_URL),
+        })],
+        false => config.detection.sources.to_owned(),
+    }
+}
+
+/// Determines the addresses of an uplink by sending the detection requests through it
+pub(crate) async fn determine_uplink(
+    config: &Config,
+    uplink: &Uplink,
+) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
+    let sources = match uplink.sources.is_empty() {
+        true => sources(config),
+        false => uplink.sources.to_owned(),
+    };
+
+    let ipv4 = async {
+        match query_sources(config, &sources, IPV4_UNSPECIFIED, Some(uplink)).await {
+            Some((IpAddr::V4(x), _)) => Some(x),
+            _ => None,
+        }
+    };
+    let ipv6 = async {
+        match query_sources(config, &sources, IPV6_UNSPECIFIED, Some(uplink)).await {
+            Some((IpAddr::V6(x), _)) => Some(x),
+            _ => None,
+        }
+    };
+
+    tokio::join!(within_budget(config, ipv4), within_budget(config, ipv6))
+}
+
+/// Queries a single source, addresses of the other family are discarded
+pub(crate) async fn query_source(
+    config: &Config,
+    source: &Source,
+    ip_enum: IpAddr,
+    uplink: Option<&Uplink>,
+) -> Option<IpAddr> {
+    let query = async {
+        match source {
+            Source::Command(x) => query_command(x, ip_enum).await,
+            Source::Dns(x) => query_dns(*x, ip_enum, uplink).await,
+            Source::File(x) => query_file(x, ip_enum).await,
+            Source::Fritzbox(x) => gateway::fritzbox(x, ip_enum).await,
+            Source::Http(x) => query_http(config, x, ip_enum, uplink).await,
+            Source::Interface(x) => query_interface(config, x, ip_enum),
+            Source::Mikrotik(x) => gateway::mikrotik(x, ip_enum).await,
+            Source::Natpmp(x) => match ip_enum {
+                IpAddr::V4(_) => gateway::natpmp(x.gateway, uplink).await.map(IpAddr::V4),
+                IpAddr::V6(_) => None,
+            },
+            Source::Static(x) => x.iter().copied().find(|x| x.is_ipv4() == ip_enum.is_ipv4()),
+            Source::Stun(x) => query_stun(x, ip_enum, uplink).await,
+            Source::Ubus(x) => gateway::ubus(x, ip_enum).await,
+            Source::Upnp => match ip_enum {
+                IpAddr::V4(_) => gateway::upnp(uplink.and_then(|x| x.local_ipv4))
+                    .await
+                    .map(IpAddr::V4),
+                IpAddr::V6(_) => None,
+            },
+            Source::Trace => {
+                let source = HttpSource {
+                    basic_auth: None,
+                    format: ResponseFormat::Text,
+                    headers: HashMap::new(),
+                    url: String::from(match ip_enum {
+                        IpAddr::V4(_) => TRACE_URL_IPV4,
+                        IpAddr::V6(_) => TRACE_URL_IPV6,
+                    }),
+                };
+                query_http(config, &source, ip_enum, uplink).await
+            }
+        }
+    };
+    let ip = match config.detection.timeout {
+        Some(seconds) => match timeout(Duration::from_secs(seconds), query).await {
+            Ok(x) => x,
+            Err(_) => {
+                output::error(t!(
+                    "detection-timeout",
+                    source = source.label(),
+                    seconds = seconds
+                ));
+                None
+            }
+        },
+        None => query.await,
+    };
+    let ip = ip.filter(|x| x.is_ipv4() == ip_enum.is_ipv4())?;
+
+    // A captive portal or broken proxy must not end up in public DNS, addresses of a carrier-grade
+    // NAT are handled separately
+    let is_public = match ip {
+        IpAddr::V4(x) => x.is_global() || x.is_shared(),
+        IpAddr::V6(x) => x.is_global(),
+    };
+    if !is_public && !config.detection.allow_private {
+        output::error(t!("detection-private", ip = ip, source = source.label()));
+        return None;
+    }
+    Some(ip)
+}
+
+/// Reads whitespace separated addresses (optionally prefixed with `ip=`) again on every query,
+/// standard input is read line by line in the background and its last address is used
+async fn query_file(path: &Path, ip_enum: IpAddr) -> Option<IpAddr> {
+    if path == Path::new("-") {
+        return query_stdin(ip_enum).await;
+    }
+
+    // Reading a FIFO waits for a writer, so the same timeout as for HTTP sources applies
+    let contents = timeout(Duration::from_secs(30), fs::read_to_string(path))
+        .await
+        .ok()?
+        .ok()?;
+
+    parse_addresses(&contents, ip_enum)
+}
+
+/// Last addresses of both families written to standard input, which can stay open to stream new
+/// addresses to the daemon
+async fn query_stdin(ip_enum: IpAddr) -> Option<IpAddr> {
+    static STDIN: OnceLock<watch::Receiver<(Option<IpAddr>, Option<IpAddr>)>> = OnceLock::new();
+
+    let mut receiver = STDIN
+        .get_or_init(|| {
+            let (sender, receiver) = watch::channel((None, None));
+            // A detached thread, a blocking read in the runtime would keep it from shutting down
+            std::thread::spawn(move || {
+                for line in std::io::stdin().lines().map_while(Result::ok) {
+                    sender.send_modify(|(ipv4, ipv6)| {
+                        *ipv4 = parse_addresses(&line, IPV4_UNSPECIFIED).or(*ipv4);
+                        *ipv6 = parse_addresses(&line, IPV6_UNSPECIFIED).or(*ipv6);
+                    });
+                }
+            });
+            receiver
+        })
+        .to_owned();
+
+    let select = |(ipv4, ipv6): &(Option<IpAddr>, Option<IpAddr>)| match ip_enum {
+        IpAddr::V4(_) => *ipv4,
+        IpAddr::V6(_) => *ipv6,
+    };
+    // The first address may still be on its way, the wait ends early if standard input is closed
+    let _ = timeout(
+        Duration::from_secs(30),
+        receiver.wait_for(|(ipv4, ipv6)| ipv4.is_some() || ipv6.is_some()),
+    )
+    .await;
+    let ip = select(&receiver.borrow());
+    ip
+}
+
+/// Runs a shell command and parses its output like the contents of a file
+async fn query_command(command: &str, ip_enum: IpAddr) -> Option<IpAddr> {
+    let mut command = match cfg!(windows) {
+        true => {
+            let mut x = Command::new("cmd");
+            x.arg("/C").arg(command);
+            x
+        }
+        false => {
+            let mut x = Command::new("sh");
+            x.arg("-c").arg(command);
+            x
+        }
+    };
+    let output = command
+        .env(
+            "DDNS_FAMILY",
+            match ip_enum {
+                IpAddr::V4(_) => "ipv4",
+                IpAddr::V6(_) => "ipv6",
+            },
+        )
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match timeout(Duration::from_secs(30), output).await {
+        Ok(Ok(x)) if x.status.success() => x.stdout,
+        _ => return None,
+    };
+
+    parse_addresses(&String::from_utf8_lossy(&output), ip_enum)
+}
 
-const IPV4_UNSPECIFIED: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
-const IPV6_UNSPECIFIED: IpAddr = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+/// Whitespace separated addresses, optionally prefixed with `ip=`
+fn parse_addresses(contents: &str, ip_enum: IpAddr) -> Option<IpAddr> {
+    contents
+        .split_whitespace()
+        .filter_map(|x| IpAddr::from_str(x.trim_start_matches("ip=")).ok())
+        .map(|x| x.to_canonical())
+        .find(|x| x.is_ipv4() == ip_enum.is_ipv4())
+}
 
-pub(crate) async fn determine_ip(config: &Config) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
-    let ipv4 = determine_ipv4(config).await;
-    let ipv6 = determine_ipv6(config).await;
-    (ipv4, ipv6)
+/// Reads the address of a local network interface, only global ones qualify
+fn query_interface(config: &Config, interface: &str, ip_enum: IpAddr) -> Option<IpAddr> {
+    let addresses = list_afinet_netifas()
+        .ok()?
+        .into_iter()
+        .filter(|(name, _)| name == interface)
+        .map(|(_, ip)| ip.to_canonical());
+
+    match ip_enum {
+        IpAddr::V4(_) => addresses.filter(|x| x.is_ipv4()).find(|x| x.is_global()),
+        IpAddr::V6(_) => {
+            let addresses = addresses
+                .filter_map(|x| match x {
+                    IpAddr::V4(_) => None,
+                    IpAddr::V6(x) => Some(translate_npt(&config.ipv6.npt, x)),
+                })
+                .filter(|x| x.is_global())
+                .collect();
+            stable_first(config, addresses)
+                .first()
+                .copied()
+                .map(IpAddr::V6)
+        }
+    }
 }
 
-pub(crate) async fn query_ip(ip_enum: IpAddr) -> Option<IpAddr> {
+/// Avoids HTTP entirely, the address family of the answer is the one of the transport
+async fn query_dns(source: DnsSource, ip_enum: IpAddr, uplink: Option<&Uplink>) -> Option<IpAddr> {
+    let (name, type_, server) = match (source, ip_enum) {
+        (DnsSource::Google, IpAddr::V4(_)) => (GOOGLE_NAME, RecordType::Txt, GOOGLE_IPV4),
+        (DnsSource::Google, IpAddr::V6(_)) => (GOOGLE_NAME, RecordType::Txt, GOOGLE_IPV6),
+        (DnsSource::Opendns, IpAddr::V4(_)) => (OPENDNS_NAME, RecordType::A, OPENDNS_IPV4),
+        (DnsSource::Opendns, IpAddr::V6(_)) => (OPENDNS_NAME, RecordType::Aaaa, OPENDNS_IPV6),
+    };
+    let local_address = match ip_enum {
+        IpAddr::V4(_) => uplink.and_then(|x| x.local_ipv4).map(IpAddr::V4),
+        IpAddr::V6(_) => uplink.and_then(|x| x.local_ipv6).map(IpAddr::V6),
+    };
+    let interface = uplink.and_then(|x| x.interface.as_deref());
+
+    let answers = dns::query_server(
+        SocketAddr::new(server, 53),
+        local_address,
+        interface,
+        name,
+        type_,
+    )
+    .await
+    .ok()?;
+
+    answers.iter().find_map(|x| {
+        x.address()
+            .or_else(|| IpAddr::from_str(x.text()?.trim()).ok())
+            .map(|x| x.to_canonical())
+    })
+}
+
+/// Sends a binding request (RFC 5389) and returns the mapped address of the response
+async fn query_stun(server: &str, ip_enum: IpAddr, uplink: Option<&Uplink>) -> Option<IpAddr> {
+    let server = lookup_host(server)
+        .await
+        .ok()?
+        .find(|x| x.is_ipv4() == ip_enum.is_ipv4())?;
+    let socket = udp_socket(server, uplink).await.ok()?;
+
+    let random = RandomState::new().build_hasher().finish().to_be_bytes();
+    let transaction_id = [
+        random,
+        RandomState::new().build_hasher().finish().to_be_bytes(),
+    ]
+    .concat()
+    .get(..12)?
+    .to_vec();
+
+    let mut request = Vec::with_capacity(20);
+    request.extend_from_slice(&STUN_BINDING_REQUEST.to_be_bytes());
+    request.extend_from_slice(&0u16.to_be_bytes());
+    request.extend_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(&transaction_id);
+
+    let response = exchange_udp(&socket, &request, |x| {
+        x.get(0..2) == Some(&STUN_BINDING_RESPONSE.to_be_bytes())
+            && x.get(8..20) == Some(transaction_id.as_slice())
+    })
+    .await?;
+
+    let length = usize::from(u16::from_be_bytes([*response.get(2)?, *response.get(3)?]));
+    let mut attributes = response.get(20..20 + length)?;
+    let mut mapped = None;
+    while attributes.len() >= 4 {
+        let type_ = u16::from_be_bytes([attributes[0], attributes[1]]);
+        let length = usize::from(u16::from_be_bytes([attributes[2], attributes[3]]));
+        let value = attributes.get(4..4 + length)?;
+        match type_ {
+            STUN_XOR_MAPPED_ADDRESS => return stun_address(value, Some(&transaction_id)),
+            STUN_MAPPED_ADDRESS => mapped = stun_address(value, None),
+            _ => {}
+        }
+        // Attributes are padded to a multiple of four bytes
+        attributes = attributes
+            .get((4 + length).next_multiple_of(4)..)
+            .unwrap_or_default();
+    }
+    mapped
+}
+
+/// Decodes a (XOR-)MAPPED-ADDRESS, the transaction ID is only needed for XOR-MAPPED-ADDRESS
+fn stun_address(value: &[u8], transaction_id: Option<&[u8]>) -> Option<IpAddr> {
+    let family = *value.get(1)?;
+    let mut address = value.get(4..)?.to_vec();
+    if let Some(transaction_id) = transaction_id {
+        let key = [STUN_MAGIC_COOKIE.to_be_bytes().as_slice(), transaction_id].concat();
+        address.iter_mut().zip(key).for_each(|(x, key)| *x ^= key);
+    }
+
+    match family {
+        0x01 => <[u8; 4]>::try_from(address.as_slice())
+            .ok()
+            .map(|x| IpAddr::V4(Ipv4Addr::from(x))),
+        0x02 => <[u8; 16]>::try_from(address.as_slice())
+            .ok()
+            .map(|x| IpAddr::V6(Ipv6Addr::from(x))),
+        _ => None,
+    }
+}
+
+/// UDP socket connected to the server, bound to the local address and interface of the uplink
+pub(crate) async fn udp_socket(
+    server: SocketAddr,
+    uplink: Option<&Uplink>,
+) -> Result<UdpSocket, IOError> {
+    let local_address = match server {
+        SocketAddr::V4(_) => uplink
+            .and_then(|x| x.local_ipv4)
+            .map_or(IPV4_UNSPECIFIED, IpAddr::V4),
+        SocketAddr::V6(_) => uplink
+            .and_then(|x| x.local_ipv6)
+            .map_or(IPV6_UNSPECIFIED, IpAddr::V6),
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(local_address, 0)).await?;
+
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    if let Some(interface) = uplink.and_then(|x| x.interface.as_deref()) {
+        socket.bind_device(Some(interface.as_bytes()))?;
+    }
+
+    socket.connect(server).await?;
+    Ok(socket)
+}
+
+/// Sends the request until a matching response arrives, retransmitting it a few times
+pub(crate) async fn exchange_udp(
+    socket: &UdpSocket,
+    request: &[u8],
+    is_response: impl Fn(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+    let mut buffer = vec![0; 2048];
+    for _ in 0..UDP_ATTEMPTS {
+        socket.send(request).await.ok()?;
+        let receive = async {
+            loop {
+                let length = socket.recv(&mut buffer).await.ok()?;
+                if let Some(x) = buffer.get(..length).filter(|x| is_response(x)) {
+                    return Some(x.to_vec());
+                }
+            }
+        };
+        if let Ok(Some(x)) = timeout(UDP_RETRANSMISSION, receive).await {
+            return Some(x);
+        }
+    }
+    None
+}
+
+async fn query_http(
+    config: &Config,
+    source: &HttpSource,
+    ip_enum: IpAddr,
+    uplink: Option<&Uplink>,
+) -> Option<IpAddr> {
     let local_ip: IpAddr = match ip_enum {
-        IpAddr::V4(_) => IPV4_UNSPECIFIED,
-        IpAddr::V6(_) => IPV6_UNSPECIFIED,
+        IpAddr::V4(_) => uplink
+            .and_then(|x| x.local_ipv4)
+            .map_or(IPV4_UNSPECIFIED, IpAddr::V4),
+        IpAddr::V6(_) => uplink
+            .and_then(|x| x.local_ipv6)
+            .map_or(IPV6_UNSPECIFIED, IpAddr::V6),
     };
 
-    let http = HttpClient::builder()
+    let is_trace =
+        [DEFAULT_SOURCE_URL, TRACE_URL_IPV4, TRACE_URL_IPV6].contains(&source.url.as_str());
+    let builder = api::client_builder(config, is_trace)
         .local_address(local_ip)
-        .timeout(Duration::from_secs(30))
-        .build()
-        .ok()?;
+        .dns_resolver(Arc::new(FamilyResolver {
+            is_ipv4: ip_enum.is_ipv4(),
+        }))
+        .timeout(Duration::from_secs(30));
+
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    let builder = match uplink.and_then(|x| x.interface.as_deref()) {
+        Some(interface) => builder.interface(interface),
+        None => builder,
+    };
 
-    let response = http
-        .get("https://cloudflare.com/cdn-cgi/trace")
-        .send()
+    let http = builder.build().ok()?;
+
+    let mut request = http.get(&source.url);
+
+    for (name, value) in &source.headers {
+        request = request.header(name, value);
+    }
+
+    if let Some(auth) = &source.basic_auth {
+        request = request.basic_auth(&auth.username, auth.password.as_ref());
+    }
+
+    let response = fixtures::send(request)
         .await
         .ok()?
+        .error_for_status()
+        .ok()?
         .text()
         .await
         .ok()?;
 
+    match &source.format {
+        ResponseFormat::Text => parse_response(&response),
+        ResponseFormat::Json(path) => {
+            let value = serde_json::from_str::<serde_json::Value>(&response).ok()?;
+            let pointer = path
+                .split('.')
+                .filter(|x| !x.is_empty())
+                .map(|x| format!("/{}", x.replace('~', "~0").replace('/', "~1")))
+                .collect::<String>();
+            let ip = value.pointer(&pointer)?.as_str()?;
+            IpAddr::from_str(ip.trim()).ok().map(|x| x.to_canonical())
+        }
+        ResponseFormat::Regex(regex) => {
+            let captures = regex.captures(&response)?;
+            let ip = captures.get(1).or_else(|| captures.get(0))?.as_str();
+            IpAddr::from_str(ip.trim()).ok().map(|x| x.to_canonical())
+        }
+    }
+}
+
+/// Resolves only the addresses of one family, the local address is not applied to connections of
+/// the other family, so e.g. the IPv6 detection would otherwise also try the A records
+struct FamilyResolver {
+    is_ipv4: bool,
+}
+
+impl Resolve for FamilyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let is_ipv4 = self.is_ipv4;
+        Box::pin(async move {
+            let addresses = lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|x| x.is_ipv4() == is_ipv4)
+                .collect::<Vec<_>>();
+            if addresses.is_empty() {
+                return Err(IOError::from(ErrorKind::AddrNotAvailable).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Accepts the key-value format of cdn-cgi/trace (`ip=...`) as well as a plain address
+fn parse_response(response: &str) -> Option<IpAddr> {
     let data: HashMap<String, String> = response
         .lines()
         .map(|x| match x.split_once('=') {
@@ -47,7 +648,10 @@ pub(crate) async fn query_ip(ip_enum: IpAddr) -> Option<IpAddr> {
         })
         .collect();
 
-    let ip = data.get("ip")?;
+    let ip = match data.get("ip") {
+        Some(x) => x.as_str(),
+        None => response.trim(),
+    };
     let ip_address = IpAddr::from_str(ip).ok()?.to_canonical();
     Some(ip_address)
 }
@@ -60,6 +664,16 @@ fn split_ipv6(ipv6: &Ipv6Addr) -> Option<([u8; 8], [u8; 8])> {
     Some((prefix, suffix))
 }
 
+/// Combines the prefix of an address with the remaining bits (e.g. subnet and interface
+/// identifier) of another one
+pub(crate) fn with_suffix(ipv6: Ipv6Addr, suffix: Ipv6Addr, prefix_length: u8) -> Ipv6Addr {
+    let mask = match prefix_length.min(128) {
+        0 => 0,
+        x => u128::MAX << (128 - x),
+    };
+    Ipv6Addr::from((u128::from(ipv6) & mask) | (u128::from(suffix) & !mask))
+}
+
 fn eui48_to_modified_eui64(eui48: &[u8; 6]) -> Option<[u8; 8]> {
     let (p, s) = eui48.split_at(3);
     let prefix: [u8; 3] = p.try_into().ok()?;
@@ -72,10 +686,98 @@ fn eui48_to_modified_eui64(eui48: &[u8; 6]) -> Option<[u8; 8]> {
     Some(modified_eui64)
 }
 
-pub(crate) async fn determine_ipv4(config: &Config) -> Option<Ipv4Addr> {
-    let _ = config;
+/// Translates an internal address according to the first matching NPTv6 mapping (RFC 6296)
+fn translate_npt(mappings: &[Npt], ipv6: Ipv6Addr) -> Ipv6Addr {
+    let mapping = match mappings
+        .iter()
+        .find(|x| x.internal.contains(&ipv6) && x.internal.prefix_len() == x.external.prefix_len())
+    {
+        Some(x) => x,
+        None => return ipv6,
+    };
+
+    let prefix_len = mapping.internal.prefix_len();
+    let mask = u128::from(mapping.internal.netmask());
+    let translated = (u128::from(mapping.external.network()) & mask) | (u128::from(ipv6) & !mask);
+    let mut segments = Ipv6Addr::from(translated).segments();
+
+    if mapping.checksum_neutral {
+        let sum_internal = ones_complement_sum(&mapping.internal.network().segments());
+        let sum_external = ones_complement_sum(&mapping.external.network().segments());
+        let adjustment = ones_complement_add(sum_internal, !sum_external);
+
+        let index = match prefix_len <= 48 {
+            true => Some(3),
+            false => (4..8).find(|&i| segments[i] != 0xffff),
+        };
+
+        if let Some(i) = index {
+            segments[i] = match ones_complement_add(segments[i], adjustment) {
+                0xffff => 0,
+                x => x,
+            };
+        }
+    }
+
+    Ipv6Addr::from(segments)
+}
+
+/// Removes temporary and deprecated addresses (after the translation) unless only those are left
+fn stable_first(config: &Config, addresses: Vec<Ipv6Addr>) -> Vec<Ipv6Addr> {
+    if !config.ipv6.prefer_stable {
+        return addresses;
+    }
 
-    let ip = query_ip(IPV4_UNSPECIFIED).await?;
+    let unstable = unstable_ipv6()
+        .into_iter()
+        .map(|x| translate_npt(&config.ipv6.npt, x))
+        .collect::<Vec<_>>();
+    let stable = addresses
+        .iter()
+        .copied()
+        .filter(|x| !unstable.contains(x))
+        .collect::<Vec<_>>();
+    match stable.is_empty() {
+        true => addresses,
+        false => stable,
+    }
+}
+
+/// Addresses flagged as temporary (RFC 8981) or deprecated by the kernel
+#[cfg(target_os = "linux")]
+fn unstable_ipv6() -> Vec<Ipv6Addr> {
+    const IFA_F_TEMPORARY: u8 = 0x01;
+    const IFA_F_DEPRECATED: u8 = 0x20;
+
+    let contents = std::fs::read_to_string("/proc/net/if_inet6").unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let address = u128::from_str_radix(fields.first()?, 16).ok()?;
+            let flags = u8::from_str_radix(fields.get(4)?, 16).ok()?;
+            (flags & (IFA_F_TEMPORARY | IFA_F_DEPRECATED) != 0).then_some(Ipv6Addr::from(address))
+        })
+        .collect()
+}
+
+/// The flags of the addresses are not available on other platforms
+#[cfg(not(target_os = "linux"))]
+fn unstable_ipv6() -> Vec<Ipv6Addr> {
+    Vec::new()
+}
+
+fn ones_complement_add(a: u16, b: u16) -> u16 {
+    let sum = u32::from(a) + u32::from(b);
+    ((sum & 0xffff) + (sum >> 16)) as u16
+}
+
+fn ones_complement_sum(words: &[u16]) -> u16 {
+    words.iter().fold(0, |acc, &x| ones_complement_add(acc, x))
+}
+
+pub(crate) async fn determine_ipv4(config: &Config) -> Option<Ipv4Addr> {
+    let (ip, _) = query_ip(config, IPV4_UNSPECIFIED).await?;
 
     let ipv4 = match ip {
         IpAddr::V4(x) => Some(x),
@@ -86,14 +788,17 @@ pub(crate) async fn determine_ipv4(config: &Config) -> Option<Ipv4Addr> {
 }
 
 pub(crate) async fn determine_ipv6(config: &Config) -> Option<Ipv6Addr> {
-    let ip = query_ip(IPV6_UNSPECIFIED).await?;
+    let (ip, is_outgoing) = query_ip(config, IPV6_UNSPECIFIED).await?;
 
     let ipv6 = match ip {
         IpAddr::V4(_) => None,
         IpAddr::V6(x) => Some(x),
     }?;
 
-    if config.ipv6.prefer_outgoing {
+    // Addresses of a router, a command, a file, an interface or the configuration are published as
+    // they are, only the outgoing address may be a temporary one that is replaced by a local
+    // address of its prefix
+    if config.ipv6.prefer_outgoing || !is_outgoing {
         return Some(ipv6);
     }
 
@@ -105,7 +810,7 @@ pub(crate) async fn determine_ipv6(config: &Config) -> Option<Ipv6Addr> {
         .cloned()
         .filter_map(|(_, ip)| match ip.to_canonical() {
             IpAddr::V4(_) => None,
-            IpAddr::V6(x) => Some(x),
+            IpAddr::V6(x) => Some(translate_npt(&config.ipv6.npt, x)),
         })
         .filter(|ip| ip.is_global())
         .filter(|ip| match split_ipv6(ip) {
@@ -113,6 +818,7 @@ pub(crate) async fn determine_ipv6(config: &Config) -> Option<Ipv6Addr> {
             None => false,
         })
         .collect::<Vec<_>>();
+    let ipv6_addresses = stable_first(config, ipv6_addresses);
 
     if ipv6_addresses.is_empty() {
         return None;
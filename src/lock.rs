@@ -0,0 +1,43 @@
+use crate::{i18n::t, output, state, structs::config::Config};
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{Error as IOError, ErrorKind},
+};
+
+/// Advisory lock next to the state file, released when dropped
+pub(crate) struct Lock {
+    _file: File,
+}
+
+/// Prevents overlapping runs (e.g. from cron), returns None if another instance holds the lock
+/// and waiting is not requested
+pub(crate) async fn acquire(config: &Config, wait: bool) -> Result<Option<Lock>, IOError> {
+    let mut path = state::path(config)?;
+    path.as_mut_os_string().push(".lock");
+    fs::create_dir_all(
+        path.parent()
+            .ok_or_else(|| IOError::from(ErrorKind::NotFound))?,
+    )?;
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+
+    match file.try_lock() {
+        Ok(()) => return Ok(Some(Lock { _file: file })),
+        Err(TryLockError::WouldBlock) => {}
+        Err(TryLockError::Error(e)) => return Err(e),
+    }
+
+    if !wait {
+        return Ok(None);
+    }
+
+    output::info(t!("lock-waiting"));
+    let file = tokio::task::spawn_blocking(move || file.lock().map(|_| file))
+        .await
+        .map_err(IOError::other)??;
+    Ok(Some(Lock { _file: file }))
+}
@@ -1,37 +1,105 @@
 #![feature(ip)]
 
+pub(crate) mod actions;
 pub(crate) mod api;
+pub(crate) mod bench;
 pub(crate) mod config;
+#[cfg(unix)]
+pub(crate) mod control;
+pub(crate) mod crash;
+pub(crate) mod daemon;
+pub(crate) mod dns;
+pub(crate) mod dyndns;
+pub(crate) mod echo;
 pub(crate) mod errors;
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+pub(crate) mod events;
+pub(crate) mod export;
+pub(crate) mod fixtures;
+pub(crate) mod gateway;
+pub(crate) mod geoip;
+pub(crate) mod ha;
+pub(crate) mod i18n;
 pub(crate) mod ip;
+pub(crate) mod lock;
+pub(crate) mod maintenance;
+pub(crate) mod output;
+pub(crate) mod prune;
+pub(crate) mod saas;
+pub(crate) mod server;
+pub(crate) mod service;
+pub(crate) mod shutdown;
+pub(crate) mod spectrum;
+pub(crate) mod startup;
+pub(crate) mod state;
 pub(crate) mod structs;
+pub(crate) mod sync;
+#[cfg(target_os = "linux")]
+pub(crate) mod systemd;
+pub(crate) mod update;
+pub(crate) mod watch;
 
-use api::{api_get, api_patch};
 use clap::Parser;
 use errors::{handle_errors, ErrorKind};
-use ip::determine_ip;
-use reqwest::{Client as HttpClient, Response, Url};
-use serde::de::DeserializeOwned;
-use serde_json::Value as Json;
-use std::{net::IpAddr, process::exit, str::FromStr};
+use std::{net::IpAddr, process::exit, time::Duration};
 use structs::{
-    cloudflare::request::PatchDnsRecord,
-    cloudflare::response::{ListDnsRecords, ListZone},
-    cloudflare::Cloudflare,
-    Args,
+    config::{Config, Family},
+    Args, Command, RunOptions, ServiceCommand, ServiceManager,
 };
 
 const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 
 #[tokio::main]
 async fn main() {
+    crash::install();
+
     let args = Args::parse();
 
+    output::set_mode(args.output);
+
+    let fixtures = match (&args.record_http, &args.replay_http) {
+        (Some(x), _) => Some(fixtures::Mode::Record(x.to_owned())),
+        (None, Some(x)) => Some(fixtures::Mode::Replay(x.to_owned())),
+        (None, None) => None,
+    };
+    if let Some(mode) = fixtures {
+        if let Err(e) = fixtures::init(mode) {
+            handle_errors(&ErrorKind::Fixtures(e));
+            exit(141)
+        }
+    }
+
     if args.version {
         println!("{}", VERSION.unwrap_or("unknown"));
         return;
     }
 
+    if let Some(Command::ServeEcho {
+        listen,
+        proxy_header,
+        trusted_proxy,
+    }) = args.command
+    {
+        let settings = echo::Settings {
+            listen,
+            proxy_header,
+            trusted_proxies: trusted_proxy,
+        };
+        if let Err(e) = echo::run(settings).await {
+            handle_errors(&ErrorKind::Server(e));
+            exit(129)
+        }
+        return;
+    }
+
+    if let Some(Command::SelfUpdate { check }) = args.command {
+        if let Err(e) = update::run(check, args.force).await {
+            handle_errors(&ErrorKind::SelfUpdate(e));
+            exit(135)
+        }
+        return;
+    }
+
     let config_path = match args.config {
         Some(x) => x,
         None => match config::path() {
@@ -48,7 +116,7 @@ async fn main() {
         return;
     }
 
-    let config = match config::get(config_path) {
+    let config = match config::get(&config_path) {
         Ok(x) => x,
         Err(e) => {
             handle_errors(&ErrorKind::Config(e));
@@ -56,250 +124,166 @@ async fn main() {
         }
     };
 
-    let (ipv4, ipv6) = determine_ip(&config).await;
-
-    if ipv4.is_none() {
-        handle_errors(&ErrorKind::IPv4)
-    };
-
-    if ipv6.is_none() {
-        handle_errors(&ErrorKind::IPv6)
-    };
+    crash::set_config(&config);
 
-    if ipv4.is_none() && ipv6.is_none() {
-        println!("Neither IPv4 nor IPv6 address could be determined");
-        exit(102)
+    api::configure(&config);
+    if let Err(e) = output::init(&config.log) {
+        handle_errors(&ErrorKind::Log(e));
+        exit(115)
     }
 
-    let http: HttpClient = HttpClient::new();
+    #[cfg(not(feature = "http3"))]
+    if config.http3 {
+        output::error(i18n::t!("warning-http3-unsupported"));
+    }
 
-    let api_base: Url = match Url::parse("https://api.cloudflare.com/client/v4/") {
-        Ok(x) => x,
-        Err(e) => {
-            handle_errors(&ErrorKind::Unknown(Box::new(e)));
-            exit(103)
-        }
-    };
+    let provided = [args.ipv4.map(IpAddr::V4), args.ipv6.map(IpAddr::V6)];
+    if let Some(ip) = provided
+        .into_iter()
+        .flatten()
+        .find(|x| !x.is_global() && !config.detection.allow_private)
+    {
+        handle_errors(&ErrorKind::ProvidedAddress(ip));
+        exit(152)
+    }
 
-    let url_list_zones = match api_base.join("zones") {
-        Ok(x) => x,
-        Err(e) => {
-            handle_errors(&ErrorKind::Unknown(Box::new(e)));
-            exit(104)
-        }
+    let options = RunOptions {
+        create_missing: args.create_missing,
+        family: match (args.ipv4_only, args.ipv6_only) {
+            (true, _) => Some(Family::Ipv4),
+            (_, true) => Some(Family::Ipv6),
+            _ => None,
+        },
+        force: args.force,
+        ipv4: args.ipv4,
+        ipv6: args.ipv6,
+        records: None,
+        refresh_cache: args.refresh_cache,
+        report_file: args.report_file,
+        wait: args.wait && !args.no_wait,
     };
+    let startup_delay = args.startup_delay.unwrap_or(config.startup_delay);
+
+    // Checks and the daemon verify the tokens after the startup delay, when the network is online
+    if !matches!(
+        args.command,
+        None | Some(Command::Daemon { .. }) | Some(Command::Service { .. })
+    ) {
+        verify_tokens(&config).await;
+    }
 
-    let response_zones = match api_get(&http, url_list_zones, &config.api_token).await {
-        Ok(x) => x,
-        Err(_) => {
-            handle_errors(&ErrorKind::Api);
-            exit(105);
+    match args.command {
+        Some(Command::Bench { iterations }) => {
+            if let Err(e) = bench::run(&config, iterations).await {
+                handle_errors(&e.kind);
+                exit(e.code)
+            }
         }
-    };
-
-    let json_zones = match deserialize_response(response_zones).await {
-        Ok(x) => x,
-        Err(e) => {
-            handle_errors(&e);
-            exit(106);
+        Some(Command::Daemon { interval }) => {
+            if let Err(e) =
+                daemon::run(&config_path, config, &options, interval, startup_delay).await
+            {
+                handle_errors(&e.kind);
+                exit(e.code)
+            }
         }
-    };
-
-    let data_zones = match deserialize_json_value::<Vec<ListZone>>(json_zones.result).await {
-        Ok(x) => x,
-        Err(e) => {
-            handle_errors(&e);
-            exit(107);
+        Some(Command::Export {
+            format,
+            zone_export,
+        }) => {
+            output::set_console(output::Console::Stderr);
+            if let Err(e) = export::run(&config, format, zone_export).await {
+                handle_errors(&e.kind);
+                exit(e.code)
+            }
         }
-    };
-
-    for config_zone in config.records.keys() {
-        let zone = match obtain_zone(&data_zones, config_zone).await {
-            Some(x) => x,
-            None => {
-                println!(
-                    "Skipping \"{}\" because the corresponding zone could not be found",
-                    &config_zone
-                );
-                continue;
+        Some(Command::Service {
+            command:
+                ServiceCommand::Install {
+                    daemon,
+                    interval,
+                    manager,
+                    print,
+                },
+        }) => {
+            let default_manager = match cfg!(target_os = "macos") {
+                true => ServiceManager::Launchd,
+                false if cfg!(windows) => ServiceManager::Windows,
+                false => ServiceManager::Systemd,
+            };
+            let settings = service::Settings {
+                daemon,
+                interval: interval.unwrap_or(config.daemon.interval),
+                manager: manager.unwrap_or(default_manager),
+                print,
+            };
+            if let Err(e) = service::install(&config_path, &config, &settings) {
+                handle_errors(&ErrorKind::Service(e));
+                exit(151)
             }
-        };
-
-        let url_list_dns_records =
-            match api_base.join(format!("zones/{}/dns_records", zone.id).as_str()) {
-                Ok(x) => x,
+        }
+        Some(Command::Prune { dry_run, yes }) => {
+            let _lock = match lock::acquire(&config, options.wait).await {
+                Ok(Some(x)) => x,
+                Ok(None) => {
+                    output::info(i18n::t!("lock-busy"));
+                    return;
+                }
                 Err(e) => {
-                    handle_errors(&ErrorKind::Unknown(Box::new(e)));
-                    exit(108)
+                    handle_errors(&ErrorKind::Lock(e));
+                    exit(150)
                 }
             };
-
-        let response_records = match api_get(&http, url_list_dns_records, &config.api_token).await {
-            Ok(x) => x,
-            Err(_) => {
-                handle_errors(&ErrorKind::Api);
-                exit(109);
+            if let Err(e) = prune::run(&config, dry_run, yes).await {
+                handle_errors(&e.kind);
+                exit(e.code)
             }
-        };
-
-        let json_records = match deserialize_response(response_records).await {
-            Ok(x) => x,
-            Err(e) => {
-                handle_errors(&e);
-                match e {
-                    ErrorKind::NoSuccessHttp | ErrorKind::NoSuccessJson => continue,
-                    _ => exit(110),
-                }
+        }
+        Some(Command::ServeDyndns { listen }) => {
+            if let Err(e) = dyndns::run(config, &options, listen).await {
+                handle_errors(&e.kind);
+                exit(e.code)
             }
-        };
-
-        let data_records =
-            match deserialize_json_value::<Vec<ListDnsRecords>>(json_records.result).await {
-                Ok(x) => x,
+        }
+        Some(Command::Watch { interval }) => {
+            if let Err(e) = watch::run(&config, &options, Duration::from_secs(interval)).await {
+                handle_errors(&ErrorKind::Terminal(e));
+                exit(116)
+            }
+        }
+        Some(Command::SelfUpdate { .. }) | Some(Command::ServeEcho { .. }) => {}
+        None => {
+            shutdown::install();
+            startup::wait(&config, startup_delay).await;
+            verify_tokens(&config).await;
+            let _lock = match lock::acquire(&config, options.wait).await {
+                Ok(Some(x)) => x,
+                Ok(None) => {
+                    output::info(i18n::t!("lock-busy"));
+                    return;
+                }
                 Err(e) => {
-                    handle_errors(&e);
-                    exit(111);
+                    handle_errors(&ErrorKind::Lock(e));
+                    exit(150)
                 }
             };
-
-        let config_records = match config.records.get(config_zone) {
-            Some(x) => x,
-            None => continue,
-        };
-
-        for config_record in config_records {
-            let record_name = match config_record == "@" {
-                true => config_zone.to_owned(),
-                false => format!("{}.{}", config_record, config_zone),
-            };
-
-            let records = obtain_records(&data_records, record_name.as_str()).await;
-
-            if records.is_empty() {
-                println!(
-                    "Skipping \"{}\" because the corresponding records could not be found",
-                    &config_record
-                );
-                continue;
-            }
-
-            'outer: for record in records {
-                let url_patch_dns_records = match api_base
-                    .join(format!("zones/{}/dns_records/{}", zone.id, record.id).as_str())
-                {
-                    Ok(x) => x,
-                    Err(e) => {
-                        handle_errors(&ErrorKind::Unknown(Box::new(e)));
-                        exit(112)
-                    }
-                };
-
-                let ip: IpAddr = match record.type_.to_uppercase().as_str() {
-                    "A" => 'inner: {
-                        if let Some(ip) = ipv4 {
-                            break 'inner IpAddr::V4(ip);
-                        }
-                        continue 'outer;
-                    }
-                    "AAAA" => 'inner: {
-                        if let Some(ip) = ipv6 {
-                            break 'inner IpAddr::V6(ip);
-                        }
-                        continue 'outer;
-                    }
-                    _ => {
-                        handle_errors(&ErrorKind::NonAddressRecord);
-                        continue;
-                    }
-                };
-
-                let msg = format!(
-                    "\"{}\" Record \"{}\" in zone \"{}\" with IP address \"{}\"",
-                    record.type_, record.name, zone.name, ip
-                );
-
-                if let Ok(current_ip) = IpAddr::from_str(&record.content) {
-                    if current_ip == ip {
-                        println!("Already up-to-date: {}", msg);
-                        continue;
-                    }
+            match sync::run(&config, &options).await {
+                Ok(report) => output::summary(&report),
+                Err(e) => {
+                    handle_errors(&e.kind);
+                    exit(e.code)
                 }
-
-                let payload = PatchDnsRecord {
-                    comment: None,
-                    content: Some(ip),
-                    name: None,
-                    proxied: None,
-                    tags: None,
-                    ttl: None,
-                };
-
-                let response_record = match api_patch(
-                    &http,
-                    url_patch_dns_records,
-                    &config.api_token,
-                    &payload,
-                )
-                .await
-                {
-                    Ok(x) => x,
-                    Err(_) => {
-                        handle_errors(&ErrorKind::Api);
-                        exit(113);
-                    }
-                };
-
-                match deserialize_response(response_record).await {
-                    Ok(x) => x,
-                    Err(e) => {
-                        handle_errors(&e);
-                        match e {
-                            ErrorKind::NoSuccessHttp | ErrorKind::NoSuccessJson => continue,
-                            _ => exit(114),
-                        }
-                    }
-                };
-
-                println!("Successfully updated: {}", msg);
             }
         }
     }
 }
 
-async fn deserialize_response(response: Response) -> Result<Cloudflare, ErrorKind> {
-    if !is_http_success(&response) {
-        return Err(ErrorKind::NoSuccessHttp);
-    }
-
-    let data = response
-        .json::<Cloudflare>()
-        .await
-        .map_err(|_| ErrorKind::Json)?;
-
-    if !data.success {
-        return Err(ErrorKind::NoSuccessJson);
+/// Exits if the token of any account is invalid
+async fn verify_tokens(config: &Config) {
+    for account in config::accounts(config) {
+        if let Err(e) = api::verify_token(&account).await {
+            handle_errors(&e);
+            exit(158)
+        }
     }
-
-    Ok(data)
-}
-
-async fn deserialize_json_value<T: DeserializeOwned>(data: Json) -> Result<T, ErrorKind> {
-    let result = serde_json::from_value::<T>(data).map_err(|_| ErrorKind::Json)?;
-    Ok(result)
-}
-
-async fn obtain_zone(data: &[ListZone], zone_name: &str) -> Option<ListZone> {
-    data.iter().find(|x| x.name == zone_name).cloned()
-}
-
-async fn obtain_records(data: &[ListDnsRecords], record_name: &str) -> Vec<ListDnsRecords> {
-    data.iter()
-        .filter(|x| x.name == record_name)
-        .filter(|x| x.type_.to_uppercase() == "A" || x.type_.to_uppercase() == "AAAA")
-        .cloned()
-        .collect()
-}
-
-fn is_http_success(response: &Response) -> bool {
-    response.status().is_success()
 }
@@ -0,0 +1,36 @@
+use crate::{
+    i18n::t,
+    output,
+    structs::config::{Maintenance, Timezone},
+};
+use chrono::{Local, Timelike, Utc};
+use croner::Cron;
+use std::str::FromStr;
+
+/// Whether changes may be applied now, which is always the case if no windows are configured
+pub(crate) fn is_open(settings: &Maintenance) -> bool {
+    if settings.windows.is_empty() {
+        return true;
+    }
+
+    let now = match settings.timezone {
+        Timezone::Local => Local::now().naive_local(),
+        Timezone::Utc => Utc::now().naive_utc(),
+    };
+    // Expressions without seconds only match at the start of a minute
+    let now = now
+        .with_second(0)
+        .and_then(|x| x.with_nanosecond(0))
+        .unwrap_or(now);
+
+    settings
+        .windows
+        .iter()
+        .any(|window| match Cron::from_str(window) {
+            Ok(cron) => cron.is_time_matching(&now).unwrap_or(false),
+            Err(e) => {
+                output::error(t!("maintenance-invalid", window = window, error = e));
+                false
+            }
+        })
+}
@@ -0,0 +1,244 @@
+use crate::{
+    i18n::t,
+    structs::{
+        config::{Log, Timezone},
+        report::Report,
+        OutputMode,
+    },
+};
+use chrono::{
+    format::{Item, StrftimeItems},
+    Local, Utc,
+};
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    fs::File,
+    io::{Error as IOError, ErrorKind, Write},
+    sync::{Mutex, TryLockError},
+};
+
+const RECENT_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Console {
+    Off,
+    Stderr,
+    Stdout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Error,
+    Info,
+    Notice,
+}
+
+struct Logger {
+    console: Console,
+    errors: Vec<String>,
+    file: Option<File>,
+    mode: OutputMode,
+    recent: VecDeque<String>,
+    settings: Option<Log>,
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger {
+    console: Console::Stdout,
+    errors: Vec::new(),
+    file: None,
+    mode: OutputMode::Human,
+    recent: VecDeque::new(),
+    settings: None,
+});
+
+pub(crate) fn init(settings: &Log) -> Result<(), IOError> {
+    if StrftimeItems::new(&settings.timestamp_format).any(|x| x == Item::Error) {
+        return Err(IOError::new(
+            ErrorKind::InvalidInput,
+            t!("error-timestamp-format", format = settings.timestamp_format),
+        ));
+    }
+
+    let file = match &settings.file {
+        Some(path) => Some(File::options().create(true).append(true).open(path)?),
+        None => None,
+    };
+
+    let mut logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    logger.file = file;
+    logger.settings = Some(settings.to_owned());
+    Ok(())
+}
+
+pub(crate) fn set_mode(mode: OutputMode) {
+    LOGGER.lock().unwrap_or_else(|e| e.into_inner()).mode = mode;
+}
+
+/// Redirects or disables console output (e.g. while a TUI owns the terminal)
+pub(crate) fn set_console(console: Console) {
+    LOGGER.lock().unwrap_or_else(|e| e.into_inner()).console = console;
+}
+
+/// Returns the most recent lines of output, oldest first
+pub(crate) fn recent() -> Vec<String> {
+    let logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    logger.recent.iter().cloned().collect()
+}
+
+/// Like `recent`, but gives up instead of blocking (e.g. while panicking with the lock held)
+pub(crate) fn try_recent() -> Option<Vec<String>> {
+    let logger = match LOGGER.try_lock() {
+        Ok(x) => x,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return None,
+    };
+    Some(logger.recent.iter().cloned().collect())
+}
+
+/// Whether errors have been output since the last call of `take_errors`
+pub(crate) fn has_errors() -> bool {
+    !LOGGER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .errors
+        .is_empty()
+}
+
+/// Returns and clears all errors that have been output since the last call
+pub(crate) fn take_errors() -> Vec<String> {
+    std::mem::take(&mut LOGGER.lock().unwrap_or_else(|e| e.into_inner()).errors)
+}
+
+pub(crate) fn info(msg: impl Display) {
+    write(Level::Info, msg);
+}
+
+/// Like `info` but highlighted in output modes that support it (e.g. successful updates)
+pub(crate) fn notice(msg: impl Display) {
+    write(Level::Notice, msg);
+}
+
+pub(crate) fn error(msg: impl Display) {
+    write(Level::Error, msg);
+}
+
+fn write(level: Level, msg: impl Display) {
+    let mut logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    let settings = logger.settings.clone().unwrap_or_default();
+
+    let line = match settings.timestamps {
+        true => format!("{} {}", timestamp(&settings), msg),
+        false => msg.to_string(),
+    };
+
+    let console_line = match (logger.mode, level) {
+        (OutputMode::Human, _) | (OutputMode::Github, Level::Info) => line.to_owned(),
+        (OutputMode::Github, Level::Error) => format!("::error::{}", escape_github(&msg)),
+        (OutputMode::Github, Level::Notice) => format!("::notice::{}", escape_github(&msg)),
+    };
+
+    match logger.console {
+        Console::Off => {}
+        Console::Stderr => eprintln!("{console_line}"),
+        Console::Stdout => println!("{console_line}"),
+    }
+
+    if let Some(file) = logger.file.as_mut() {
+        let _ = writeln!(file, "{line}");
+    }
+
+    if level == Level::Error {
+        logger.errors.push(msg.to_string());
+    }
+
+    if logger.recent.len() == RECENT_CAPACITY {
+        logger.recent.pop_front();
+    }
+    logger.recent.push_back(line);
+}
+
+fn timestamp(settings: &Log) -> String {
+    let format = settings.timestamp_format.as_str();
+    match settings.timezone {
+        Timezone::Local => Local::now().format(format).to_string(),
+        Timezone::Utc => Utc::now().format(format).to_string(),
+    }
+}
+
+/// Workflow commands of GitHub Actions have to be on a single line
+fn escape_github(msg: impl Display) -> String {
+    msg.to_string()
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Appends a markdown table of the run to the job summary when running in GitHub Actions
+pub(crate) fn summary(report: &Report) {
+    let mode = LOGGER.lock().unwrap_or_else(|e| e.into_inner()).mode;
+    if mode != OutputMode::Github {
+        return;
+    }
+
+    let path = match std::env::var_os("GITHUB_STEP_SUMMARY") {
+        Some(x) => x,
+        None => return,
+    };
+
+    let mut markdown = String::from("## DDNS Cloudflare\n\n");
+    markdown.push_str(
+        format!(
+            "- IPv4: `{}`\n- IPv6: `{}`\n\n",
+            report
+                .ipv4
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| String::from("-")),
+            report
+                .ipv6
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| String::from("-"))
+        )
+        .as_str(),
+    );
+    markdown.push_str(
+        format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            t!("column-zone"),
+            t!("column-name"),
+            t!("column-type"),
+            t!("column-content"),
+            t!("column-desired"),
+            t!("column-status")
+        )
+        .as_str(),
+    );
+    markdown.push_str("| --- | --- | --- | --- | --- | --- |\n");
+    for record in &report.records {
+        markdown.push_str(
+            format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                record.zone,
+                record.name,
+                record.type_,
+                record.content,
+                record
+                    .desired
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|| String::from("-")),
+                record.status()
+            )
+            .as_str(),
+        );
+    }
+
+    let result = File::options()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{markdown}"));
+
+    if let Err(e) = result {
+        error(format!("{}\n{e}", t!("error-summary")));
+    }
+}
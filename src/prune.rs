@@ -0,0 +1,196 @@
+use crate::{
+    api::{
+        api_delete, api_get, api_get_all, client, deserialize_json_value, deserialize_response,
+        Auth, ZONES_PER_PAGE,
+    },
+    config,
+    errors::{handle_errors, ErrorKind, Failure},
+    i18n::t,
+    output, state,
+    structs::{
+        cloudflare::response::{ListDnsRecords, ListZone},
+        config::Config,
+    },
+};
+use reqwest::{Client as HttpClient, Url};
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, IsTerminal, Write},
+};
+
+/// Deletes the records that have been published by this tool (according to the state file) but
+/// whose names are no longer configured
+pub(crate) async fn run(config: &Config, dry_run: bool, yes: bool) -> Result<(), Failure> {
+    let state_path = state::path(config).map_err(|e| Failure::new(ErrorKind::State(e), 162))?;
+    let mut state = state::load(&state_path).map_err(|e| Failure::new(ErrorKind::State(e), 162))?;
+
+    let configured = config::all_records(config)
+        .into_iter()
+        .map(|x| x.name)
+        .collect::<HashSet<_>>();
+    let mut stale = state
+        .records
+        .iter()
+        .filter(|(_, x)| !configured.contains(&x.name.to_lowercase()))
+        .map(|(id, x)| (id.to_owned(), x.to_owned()))
+        .collect::<Vec<_>>();
+    if stale.is_empty() {
+        output::info(t!("prune-nothing"));
+        return Ok(());
+    }
+    stale.sort_by(|(_, a), (_, b)| (&a.name, &a.type_).cmp(&(&b.name, &b.type_)));
+
+    let http: HttpClient =
+        client(config).map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 163))?;
+    let api_base: Url = Url::parse("https://api.cloudflare.com/client/v4/")
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 163))?;
+
+    // The records are deleted with the account whose zone contains them
+    let accounts = config::accounts(config);
+    let mut zones = Vec::new();
+    for account in &accounts {
+        zones.extend(
+            list_zones(&http, &api_base, account)
+                .await?
+                .into_iter()
+                .map(|x| (account, x)),
+        );
+    }
+
+    let mut candidates = Vec::new();
+    for (id, published) in stale {
+        let name = published.name.to_lowercase();
+        let zone = zones
+            .iter()
+            .filter(|(_, x)| name == x.name || name.ends_with(&format!(".{}", x.name)))
+            .max_by_key(|(_, x)| x.name.len());
+        let Some((account, zone)) = zone else {
+            output::error(t!("zone-not-found", zone = published.name));
+            continue;
+        };
+        let msg = t!(
+            "record-content",
+            record_type = published.type_,
+            name = published.name,
+            zone = zone.name,
+            content = published.content
+        );
+
+        // Records that someone else has taken over since are left alone
+        if account.ownership.marker.is_some() {
+            let url = api_base
+                .join(format!("zones/{}/dns_records/{id}", zone.id).as_str())
+                .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 163))?;
+            let response = api_get(&http, url, Auth::of(account))
+                .await
+                .map_err(|_| Failure::new(ErrorKind::Api, 164))?;
+            if response.status() != 404 {
+                let record = match deserialize_response(response).await {
+                    Ok(x) => deserialize_json_value::<ListDnsRecords>(x.result)
+                        .await
+                        .map_err(|e| Failure::new(e, 165))?,
+                    Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+                        handle_errors(&e);
+                        continue;
+                    }
+                    Err(e) => return Err(Failure::new(e, 165)),
+                };
+                if !config::is_owned(account, record.comment.as_deref()) {
+                    output::info(t!("ownership-refused", record = msg));
+                    continue;
+                }
+            }
+        }
+        output::info(t!("prune-candidate", record = msg));
+        candidates.push((id, *account, zone, msg));
+    }
+
+    if dry_run || candidates.is_empty() {
+        return Ok(());
+    }
+    if !yes && !confirm(candidates.len()) {
+        output::info(t!("prune-aborted"));
+        return Ok(());
+    }
+
+    for (id, account, zone, msg) in candidates {
+        let url = api_base
+            .join(format!("zones/{}/dns_records/{id}", zone.id).as_str())
+            .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 163))?;
+        let response = api_delete(&http, url, Auth::of(account))
+            .await
+            .map_err(|_| Failure::new(ErrorKind::Api, 164))?;
+
+        // Records that have been deleted by someone else are forgotten as well
+        if response.status() == 404 {
+            output::info(t!("prune-missing", record = msg));
+            state.records.remove(&id);
+            continue;
+        }
+        match deserialize_response(response).await {
+            Ok(_) => {
+                output::notice(t!("prune-deleted", record = msg));
+                state.records.remove(&id);
+            }
+            Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+                handle_errors(&e)
+            }
+            Err(e) => return Err(Failure::new(e, 165)),
+        }
+    }
+
+    state::save(&state_path, &state).map_err(|e| Failure::new(ErrorKind::State(e), 162))
+}
+
+/// Configured and listed zones of an account, tokens that can't list the zones only have the
+/// configured ones
+async fn list_zones(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+) -> Result<Vec<ListZone>, Failure> {
+    let mut zones = config::configured_zones(config);
+
+    let url_list_zones = api_base
+        .join("zones")
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 163))?;
+    let json_zones = match api_get_all(http, url_list_zones, Auth::of(config), ZONES_PER_PAGE)
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 164))?
+    {
+        Ok(x) => x,
+        Err(e) => {
+            handle_errors(&e);
+            return Ok(zones);
+        }
+    };
+    let listed = deserialize_json_value::<Vec<ListZone>>(json_zones.result)
+        .await
+        .map_err(|e| Failure::new(e, 165))?;
+    for zone in listed {
+        if !zones.iter().any(|x| x.name == zone.name) {
+            zones.push(zone);
+        }
+    }
+
+    Ok(zones)
+}
+
+/// Asks on the terminal, without a terminal the deletion has to be confirmed with --yes
+fn confirm(count: usize) -> bool {
+    if !io::stdin().is_terminal() {
+        output::error(t!("prune-unconfirmed"));
+        return false;
+    }
+
+    print!("{} ", t!("prune-confirm", count = count));
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(
+        answer.trim().to_lowercase().as_str(),
+        "y" | "yes" | "j" | "ja"
+    )
+}
@@ -0,0 +1,156 @@
+use crate::{
+    api::{api_get, api_patch, api_put, deserialize_json_value, deserialize_response, Auth},
+    errors::{handle_errors, ErrorKind, Failure},
+    i18n::t,
+    output,
+    structs::{
+        cloudflare::request::{PatchCustomHostname, PutFallbackOrigin},
+        cloudflare::response::{FallbackOrigin, ListCustomHostnames, ListZone},
+        config::{Config, ZoneSettings},
+    },
+};
+use reqwest::{Client as HttpClient, Url};
+
+/// Points the Cloudflare for SaaS fallback origin and custom origin servers of a zone at the configured hostnames
+pub(crate) async fn update(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    settings: &ZoneSettings,
+) -> Result<(), Failure> {
+    if let Some(origin) = &settings.fallback_origin {
+        update_fallback_origin(http, api_base, config, zone, origin).await?;
+    }
+
+    for (hostname, origin) in &settings.custom_origins {
+        update_custom_origin(http, api_base, config, zone, hostname, origin).await?;
+    }
+
+    Ok(())
+}
+
+async fn update_fallback_origin(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    origin: &str,
+) -> Result<(), Failure> {
+    let url = api_base
+        .join(format!("zones/{}/custom_hostnames/fallback_origin", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 141))?;
+
+    let response = api_get(http, url.to_owned(), Auth::of(config))
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 142))?;
+
+    // A zone without fallback origin responds with an error, it is then created below
+    let current = match deserialize_response(response).await {
+        Ok(x) => deserialize_json_value::<FallbackOrigin>(x.result)
+            .await
+            .ok()
+            .and_then(|x| x.origin),
+        Err(_) => None,
+    };
+
+    if current.as_deref() == Some(origin) {
+        output::info(t!(
+            "fallback-origin-up-to-date",
+            zone = zone.name,
+            origin = origin
+        ));
+        return Ok(());
+    }
+
+    let payload = PutFallbackOrigin {
+        origin: origin.to_owned(),
+    };
+    let response = api_put(http, url, Auth::of(config), &payload)
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 142))?;
+
+    match deserialize_response(response).await {
+        Ok(_) => output::notice(t!(
+            "fallback-origin-updated",
+            zone = zone.name,
+            origin = origin
+        )),
+        Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => handle_errors(&e),
+        Err(e) => return Err(Failure::new(e, 143)),
+    }
+
+    Ok(())
+}
+
+async fn update_custom_origin(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    hostname: &str,
+    origin: &str,
+) -> Result<(), Failure> {
+    let mut url = api_base
+        .join(format!("zones/{}/custom_hostnames", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 141))?;
+    url.query_pairs_mut().append_pair("hostname", hostname);
+
+    let response = api_get(http, url, Auth::of(config))
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 142))?;
+
+    let custom_hostnames = match deserialize_response(response).await {
+        Ok(x) => deserialize_json_value::<Vec<ListCustomHostnames>>(x.result)
+            .await
+            .map_err(|e| Failure::new(e, 143))?,
+        Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+            handle_errors(&e);
+            return Ok(());
+        }
+        Err(e) => return Err(Failure::new(e, 143)),
+    };
+
+    let custom_hostname = match custom_hostnames.iter().find(|x| x.hostname == hostname) {
+        Some(x) => x,
+        None => {
+            output::error(t!(
+                "custom-hostname-not-found",
+                hostname = hostname,
+                zone = zone.name
+            ));
+            return Ok(());
+        }
+    };
+
+    if custom_hostname.custom_origin_server.as_deref() == Some(origin) {
+        output::info(t!(
+            "custom-origin-up-to-date",
+            hostname = hostname,
+            origin = origin
+        ));
+        return Ok(());
+    }
+
+    let url = api_base
+        .join(format!("zones/{}/custom_hostnames/{}", zone.id, custom_hostname.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 141))?;
+    let payload = PatchCustomHostname {
+        custom_origin_server: origin.to_owned(),
+    };
+    let response = api_patch(http, url, Auth::of(config), &payload)
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 142))?;
+
+    match deserialize_response(response).await {
+        Ok(_) => output::notice(t!(
+            "custom-origin-updated",
+            hostname = hostname,
+            origin = origin
+        )),
+        Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => handle_errors(&e),
+        Err(e) => return Err(Failure::new(e, 143)),
+    }
+
+    Ok(())
+}
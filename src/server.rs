@@ -0,0 +1,162 @@
+use std::{future::Future, io::Error as IOError, net::SocketAddr, sync::Arc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+const MAX_REQUEST_SIZE: usize = 16 * 1024;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub headers: Vec<(String, String)>,
+    pub method: String,
+    pub peer: SocketAddr,
+    pub target: String,
+}
+
+impl Request {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        match self.target.split_once('?') {
+            Some((path, _)) => path,
+            None => self.target.as_str(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    pub body: String,
+    pub content_type: &'static str,
+    pub headers: Vec<(String, String)>,
+    pub status: u16,
+}
+
+impl Response {
+    pub(crate) fn text(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            content_type: "text/plain; charset=utf-8",
+            headers: Vec::new(),
+            status,
+        }
+    }
+
+    pub(crate) fn json(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            content_type: "application/json",
+            headers: Vec::new(),
+            status,
+        }
+    }
+}
+
+/// Serves HTTP/1.1 requests until an error occurs while accepting connections
+pub(crate) async fn serve<H, F>(listener: TcpListener, handler: H) -> Result<(), IOError>
+where
+    H: Fn(Request) -> F + Send + Sync + 'static,
+    F: Future<Output = Response> + Send,
+{
+    let handler = Arc::new(handler);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            let _ = handle_connection(stream, peer, handler.as_ref()).await;
+        });
+    }
+}
+
+async fn handle_connection<H, F>(
+    mut stream: TcpStream,
+    peer: SocketAddr,
+    handler: &H,
+) -> Result<(), IOError>
+where
+    H: Fn(Request) -> F,
+    F: Future<Output = Response>,
+{
+    let response = match read_request(&mut stream, peer).await? {
+        Some(request) => handler(request).await,
+        None => Response::text(400, "Bad Request\n"),
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(format!("{name}: {value}\r\n").as_str());
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn read_request(
+    stream: &mut TcpStream,
+    peer: SocketAddr,
+) -> Result<Option<Request>, IOError> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+
+    while !buffer.windows(4).any(|x| x == b"\r\n\r\n") {
+        if buffer.len() > MAX_REQUEST_SIZE {
+            return Ok(None);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+
+    let text = String::from_utf8_lossy(&buffer);
+    let mut lines = text.split("\r\n");
+
+    let mut request_line = match lines.next() {
+        Some(x) => x.split_whitespace(),
+        None => return Ok(None),
+    };
+    let (method, target) = match (request_line.next(), request_line.next()) {
+        (Some(method), Some(target)) => (method.to_owned(), target.to_owned()),
+        _ => return Ok(None),
+    };
+
+    let headers = lines
+        .take_while(|x| !x.is_empty())
+        .filter_map(|x| x.split_once(':'))
+        .map(|(name, value)| (name.trim().to_owned(), value.trim().to_owned()))
+        .collect();
+
+    Ok(Some(Request {
+        headers,
+        method,
+        peer,
+        target,
+    }))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        _ => "Unknown",
+    }
+}
@@ -0,0 +1,384 @@
+use crate::{
+    i18n::t,
+    output, state,
+    structs::{
+        config::{Config, Source},
+        ServiceManager,
+    },
+};
+use std::{
+    env, fs,
+    io::Error as IOError,
+    path::{self, Path, PathBuf},
+};
+
+const NAME: &str = "ddns-cloudflare";
+const LAUNCHD_LABEL: &str = "com.github.masterflitzer.ddns-cloudflare";
+
+pub(crate) struct Settings {
+    /// Run the daemon permanently instead of periodic single runs
+    pub daemon: bool,
+    pub interval: u64,
+    pub manager: ServiceManager,
+    /// Print the files or commands instead of installing them
+    pub print: bool,
+}
+
+/// Installs the service pointing at the current executable and configuration
+pub(crate) fn install(
+    config_path: &Path,
+    config: &Config,
+    settings: &Settings,
+) -> Result<(), IOError> {
+    let executable = env::current_exe()?;
+    let config_path = path::absolute(config_path)?;
+
+    match settings.manager {
+        ServiceManager::Systemd => {
+            let mut files = vec![(
+                format!("{NAME}.service"),
+                systemd_service(&executable, &config_path, config, settings)?,
+            )];
+            if !settings.daemon {
+                files.push((format!("{NAME}.timer"), systemd_timer(settings.interval)));
+            }
+            let unit = match settings.daemon {
+                true => format!("{NAME}.service"),
+                false => format!("{NAME}.timer"),
+            };
+            write(Path::new("/etc/systemd/system"), &files, settings.print)?;
+            if !settings.print {
+                output::info(t!(
+                    "service-enable",
+                    command = format!("systemctl daemon-reload && systemctl enable --now {unit}")
+                ));
+            }
+        }
+        ServiceManager::Launchd => {
+            let files = [(
+                format!("{LAUNCHD_LABEL}.plist"),
+                launchd_plist(&executable, &config_path, settings),
+            )];
+            write(Path::new("/Library/LaunchDaemons"), &files, settings.print)?;
+            if !settings.print {
+                output::info(t!(
+                    "service-enable",
+                    command = format!(
+                        "launchctl bootstrap system /Library/LaunchDaemons/{LAUNCHD_LABEL}.plist"
+                    )
+                ));
+            }
+        }
+        ServiceManager::Windows => register_task(&executable, &config_path, settings)?,
+    }
+
+    Ok(())
+}
+
+fn write(directory: &Path, files: &[(String, String)], print: bool) -> Result<(), IOError> {
+    for (name, contents) in files {
+        let path = directory.join(name);
+        if print {
+            println!("# {}\n{contents}", path.display());
+            continue;
+        }
+        fs::write(&path, contents)?;
+        output::info(t!("service-written", path = path.display()));
+    }
+    Ok(())
+}
+
+fn systemd_service(
+    executable: &Path,
+    config_path: &Path,
+    config: &Config,
+    settings: &Settings,
+) -> Result<String, IOError> {
+    let mut command = format!(
+        "{} --config {}",
+        systemd_quote(executable),
+        systemd_quote(config_path)
+    );
+    let kind = match settings.daemon {
+        true => {
+            command.push_str(" daemon");
+            "notify"
+        }
+        false => "oneshot",
+    };
+
+    // Everything is read-only except for the state directory and the files the tool writes
+    let mut writable = vec![state::path(config)?];
+    writable.extend(config.log.file.iter().cloned());
+    writable.extend(config.daemon.socket.iter().cloned());
+    writable.extend(
+        config
+            .actions
+            .templates
+            .iter()
+            .map(|x| x.destination.to_owned()),
+    );
+    let mut directories = writable
+        .iter()
+        .filter_map(|x| path::absolute(x).ok()?.parent().map(PathBuf::from))
+        .collect::<Vec<_>>();
+    directories.sort();
+    directories.dedup();
+    let read_write_paths = directories
+        .iter()
+        .map(|x| format!("-{}", systemd_quote(x)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // Commands of actions (e.g. reloading a VPN) and command sources usually need capabilities,
+    // binding to the interface of an uplink needs CAP_NET_RAW
+    let has_commands = config.actions.templates.iter().any(|x| x.command.is_some())
+        || config.actions.failover.is_some()
+        || config
+            .detection
+            .sources
+            .iter()
+            .chain(config.uplinks.values().flat_map(|x| &x.sources))
+            .any(|x| matches!(x, Source::Command(_)));
+    let capabilities = match (
+        has_commands,
+        config.uplinks.values().any(|x| x.interface.is_some()),
+    ) {
+        (true, _) => None,
+        (false, true) => Some("CAP_NET_RAW"),
+        (false, false) => Some(""),
+    };
+
+    let mut unit = format!(
+        "[Unit]
+Description=Update the DNS records at Cloudflare
+Wants=network-online.target
+After=network-online.target
+
+[Service]
+Type={kind}
+ExecStart={command}
+"
+    );
+    if settings.daemon {
+        unit.push_str("WatchdogSec=120\nRestart=on-failure\nRestartSec=30\n");
+    }
+    unit.push_str(
+        "NoNewPrivileges=yes
+PrivateTmp=yes
+PrivateDevices=yes
+ProtectSystem=strict
+ProtectHome=read-only
+ProtectClock=yes
+ProtectControlGroups=yes
+ProtectHostname=yes
+ProtectKernelLogs=yes
+ProtectKernelModules=yes
+ProtectKernelTunables=yes
+RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6 AF_NETLINK
+RestrictNamespaces=yes
+RestrictRealtime=yes
+RestrictSUIDSGID=yes
+LockPersonality=yes
+MemoryDenyWriteExecute=yes
+SystemCallArchitectures=native
+",
+    );
+    if let Some(x) = capabilities {
+        unit.push_str(&format!("CapabilityBoundingSet={x}\n"));
+    }
+    unit.push_str(&format!("ReadWritePaths={read_write_paths}\n"));
+    if settings.daemon {
+        unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+    }
+
+    Ok(unit)
+}
+
+fn systemd_timer(interval: u64) -> String {
+    format!(
+        "[Unit]
+Description=Update the DNS records at Cloudflare periodically
+
+[Timer]
+OnBootSec=1min
+OnUnitActiveSec={interval}s
+
+[Install]
+WantedBy=timers.target
+"
+    )
+}
+
+/// Quotes a path for the command lines and path lists of systemd units
+fn systemd_quote(path: &Path) -> String {
+    let escaped = path
+        .display()
+        .to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{escaped}\"")
+}
+
+fn launchd_plist(executable: &Path, config_path: &Path, settings: &Settings) -> String {
+    let mut arguments = vec![
+        xml_escape(&executable.display().to_string()),
+        String::from("--config"),
+        xml_escape(&config_path.display().to_string()),
+    ];
+    let schedule = match settings.daemon {
+        true => {
+            arguments.push(String::from("daemon"));
+            String::from("<key>KeepAlive</key>\n    <true/>")
+        }
+        false => format!(
+            "<key>StartInterval</key>\n    <integer>{}</integer>",
+            settings.interval
+        ),
+    };
+    let arguments = arguments
+        .iter()
+        .map(|x| format!("        <string>{x}</string>"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    {schedule}
+    <key>ProcessType</key>
+    <string>Background</string>
+    <key>StandardOutPath</key>
+    <string>/var/log/{NAME}.log</string>
+    <key>StandardErrorPath</key>
+    <string>/var/log/{NAME}.log</string>
+</dict>
+</plist>
+"#
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The executable does not implement the protocol of the service control manager, so it is
+/// registered as scheduled task of the SYSTEM account instead. The task is defined as XML, the
+/// options of `schtasks.exe` would stop the daemon after the default limit of 72 hours
+fn register_task(
+    executable: &Path,
+    config_path: &Path,
+    settings: &Settings,
+) -> Result<(), IOError> {
+    let path = env::temp_dir().join(format!("{NAME}.xml"));
+    let definition = task_xml(executable, config_path, settings);
+    let arguments = [
+        "/Create",
+        "/F",
+        "/TN",
+        NAME,
+        "/XML",
+        &path.display().to_string(),
+    ];
+
+    if settings.print {
+        println!("# {}\n{definition}", path.display());
+        println!("schtasks.exe {}", arguments.join(" "));
+        return Ok(());
+    }
+
+    // Task Scheduler expects the encoding of the declaration, UTF-16 with byte order mark
+    let contents = [0xfeff]
+        .into_iter()
+        .chain(definition.encode_utf16())
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+    fs::write(&path, contents)?;
+    let status = std::process::Command::new("schtasks.exe")
+        .args(arguments)
+        .status();
+    let _ = fs::remove_file(&path);
+    let status = status?;
+    if !status.success() {
+        return Err(IOError::other(status.to_string()));
+    }
+    output::info(t!("service-registered", name = NAME));
+    Ok(())
+}
+
+/// The daemon runs without time limit and is restarted after a failure, single runs are started
+/// every interval and stopped after an hour
+fn task_xml(executable: &Path, config_path: &Path, settings: &Settings) -> String {
+    let mut arguments = format!("--config \"{}\"", config_path.display());
+    let (trigger, time_limit) = match settings.daemon {
+        true => {
+            arguments.push_str(" daemon");
+            (String::from("<BootTrigger />"), "PT0S")
+        }
+        false => (
+            format!(
+                "<TimeTrigger>
+      <StartBoundary>2000-01-01T00:00:00</StartBoundary>
+      <Repetition>
+        <Interval>PT{}M</Interval>
+      </Repetition>
+    </TimeTrigger>",
+                (settings.interval / 60).clamp(1, 1439)
+            ),
+            "PT1H",
+        ),
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>Update the DNS records at Cloudflare</Description>
+  </RegistrationInfo>
+  <Triggers>
+    {trigger}
+  </Triggers>
+  <Principals>
+    <Principal id="SYSTEM">
+      <UserId>S-1-5-18</UserId>
+      <RunLevel>HighestAvailable</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <StartWhenAvailable>true</StartWhenAvailable>
+    <ExecutionTimeLimit>{time_limit}</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+  </Settings>
+  <Actions Context="SYSTEM">
+    <Exec>
+      <Command>{}</Command>
+      <Arguments>{}</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        xml_escape(&executable.display().to_string()),
+        xml_escape(&arguments)
+    )
+}
@@ -0,0 +1,40 @@
+use crate::{i18n::t, output};
+use std::{
+    process::exit,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Lets a run finish the record it is updating and save the state on Ctrl-C or SIGTERM
+pub(crate) fn install() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            request();
+        }
+    });
+
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        if let Ok(mut term) = signal(SignalKind::terminate()) {
+            while term.recv().await.is_some() {
+                request();
+            }
+        }
+    });
+}
+
+/// The first request stops after the current record, a second one exits immediately
+pub(crate) fn request() {
+    if REQUESTED.swap(true, Ordering::SeqCst) {
+        output::error(t!("shutdown-forced"));
+        exit(130);
+    }
+    output::info(t!("shutdown-requested"));
+}
+
+pub(crate) fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
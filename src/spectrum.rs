@@ -0,0 +1,101 @@
+use crate::{
+    api::{api_get, api_patch, deserialize_json_value, deserialize_response, Auth},
+    errors::{handle_errors, ErrorKind, Failure},
+    i18n::t,
+    output,
+    structs::{
+        cloudflare::request::PatchSpectrumApp,
+        cloudflare::response::{ListZone, SpectrumApp},
+        config::{Config, Protocol},
+        report::Report,
+    },
+    sync::obtain_zone,
+};
+use reqwest::{Client as HttpClient, Url};
+use std::net::{IpAddr, SocketAddr};
+
+/// Points the direct origin of the configured Spectrum applications at the detected addresses
+pub(crate) async fn update(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zones: &[ListZone],
+    report: &Report,
+) -> Result<(), Failure> {
+    for app in &config.spectrum.apps {
+        let (ipv4, ipv6) = match &app.uplink {
+            Some(uplink) => match report.uplinks.get(uplink) {
+                Some(x) => (x.ipv4, x.ipv6),
+                None => {
+                    output::error(t!("uplink-unknown", uplink = uplink, name = app.id));
+                    continue;
+                }
+            },
+            None => (report.ipv4, report.ipv6),
+        };
+
+        let ip = match app.ipv6 {
+            true => ipv6.map(IpAddr::V6),
+            false => ipv4.map(IpAddr::V4),
+        };
+        let ip = match ip {
+            Some(x) => x,
+            None => continue,
+        };
+
+        let zone = match obtain_zone(zones, &app.zone).await {
+            Some(x) => x,
+            None => {
+                output::info(t!("zone-not-found", zone = app.zone));
+                continue;
+            }
+        };
+
+        let protocol = match app.protocol {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+        };
+        let origin = format!("{protocol}://{}", SocketAddr::new(ip, app.port));
+
+        let url = api_base
+            .join(format!("zones/{}/spectrum/apps/{}", zone.id, app.id).as_str())
+            .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 144))?;
+
+        let response = api_get(http, url.to_owned(), Auth::of(config))
+            .await
+            .map_err(|_| Failure::new(ErrorKind::Api, 145))?;
+
+        let current = match deserialize_response(response).await {
+            Ok(x) => deserialize_json_value::<SpectrumApp>(x.result)
+                .await
+                .map_err(|e| Failure::new(e, 146))?,
+            Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+                handle_errors(&e);
+                continue;
+            }
+            Err(e) => return Err(Failure::new(e, 146)),
+        };
+
+        if current.origin_direct == [origin.as_str()] {
+            output::info(t!("spectrum-up-to-date", app = app.id, origin = origin));
+            continue;
+        }
+
+        let payload = PatchSpectrumApp {
+            origin_direct: vec![origin.to_owned()],
+        };
+        let response = api_patch(http, url, Auth::of(config), &payload)
+            .await
+            .map_err(|_| Failure::new(ErrorKind::Api, 145))?;
+
+        match deserialize_response(response).await {
+            Ok(_) => output::notice(t!("spectrum-updated", app = app.id, origin = origin)),
+            Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+                handle_errors(&e)
+            }
+            Err(e) => return Err(Failure::new(e, 146)),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use crate::{i18n::t, ip, output, shutdown, structs::config::Config};
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+/// Pause between the detection attempts while waiting to be online
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Delays the first check, e.g. until the WAN link is up after booting
+pub(crate) async fn wait(config: &Config, delay: u64) {
+    if delay > 0 {
+        output::info(t!("startup-delay", seconds = delay));
+        pause(Instant::now() + Duration::from_secs(delay)).await;
+    }
+
+    if config.wait_online == 0 || shutdown::requested() {
+        return;
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(config.wait_online);
+    let mut is_waiting = false;
+    loop {
+        let (ipv4, ipv6) = ip::determine_ip(config, config.family).await;
+        if ipv4.is_some() || ipv6.is_some() {
+            if is_waiting {
+                output::info(t!("startup-online"));
+            }
+            return;
+        }
+
+        if Instant::now() >= deadline {
+            output::error(t!("startup-offline", seconds = config.wait_online));
+            return;
+        }
+        if !is_waiting {
+            output::info(t!("startup-waiting", seconds = config.wait_online));
+            is_waiting = true;
+        }
+
+        pause(deadline.min(Instant::now() + RETRY_INTERVAL)).await;
+        if shutdown::requested() {
+            return;
+        }
+    }
+}
+
+/// Sleeps in steps of a second to stop early when a shutdown is requested
+async fn pause(until: Instant) {
+    while Instant::now() < until && !shutdown::requested() {
+        sleep((until - Instant::now()).min(Duration::from_secs(1))).await;
+    }
+}
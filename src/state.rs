@@ -0,0 +1,55 @@
+use crate::{
+    config,
+    structs::{config::Config, state::State},
+};
+use directories::ProjectDirs;
+use std::{
+    fs,
+    io::{Error as IOError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+pub(crate) fn path(config: &Config) -> Result<PathBuf, IOError> {
+    if let Some(path) = &config.state_file {
+        return Ok(path.to_owned());
+    }
+
+    let mut path = data_dir()?;
+    path.push("state.json");
+    Ok(path)
+}
+
+pub(crate) fn data_dir() -> Result<PathBuf, IOError> {
+    let name = config::cargo_name();
+
+    let project_dirs =
+        ProjectDirs::from("", "", &name).ok_or_else(|| IOError::from(ErrorKind::NotFound))?;
+    Ok(PathBuf::from(ProjectDirs::data_dir(&project_dirs)))
+}
+
+/// A missing state file results in an empty state
+pub(crate) fn load(path: impl AsRef<Path>) -> Result<State, IOError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(x) => x,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(State::default()),
+        Err(e) => return Err(e),
+    };
+
+    let state: State = serde_json::from_str(&contents).map_err(IOError::other)?;
+    Ok(state)
+}
+
+pub(crate) fn save(path: impl AsRef<Path>, state: &State) -> Result<(), IOError> {
+    let path = path.as_ref();
+    fs::create_dir_all(
+        path.parent()
+            .ok_or_else(|| IOError::from(ErrorKind::NotFound))?,
+    )?;
+
+    let contents = serde_json::to_string_pretty(state).map_err(IOError::other)?;
+
+    let mut temporary = PathBuf::from(path);
+    temporary.as_mut_os_string().push(".new");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
@@ -1,25 +1,202 @@
 pub(crate) mod cloudflare;
 pub(crate) mod config;
+pub(crate) mod fixture;
+pub(crate) mod github;
+pub(crate) mod report;
+pub(crate) mod state;
 
-use clap::Parser;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueEnum};
+use config::Family;
+use std::{
+    collections::HashSet,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+};
 
 #[derive(Debug, Parser)]
 pub(crate) struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
     /// Use alternative configuration file
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
     /// Print location of configuration file
     #[arg(long)]
     pub configuration: bool,
+    /// Create the records of configured names that don't exist yet (overrides the configuration)
+    #[arg(long, global = true)]
+    pub create_missing: bool,
+    /// Overwrite records even if they have been changed by someone else (self-update: install even if not newer)
+    #[arg(long, global = true)]
+    pub force: bool,
+    /// Publish this IPv4 address instead of detecting the addresses
+    #[arg(long, global = true, env = "DDNS_IPV4")]
+    pub ipv4: Option<Ipv4Addr>,
+    /// Publish this IPv6 address instead of detecting the addresses
+    #[arg(long, global = true, env = "DDNS_IPV6")]
+    pub ipv6: Option<Ipv6Addr>,
+    /// Only determine and publish the IPv4 address (A records)
+    #[arg(long, global = true, conflicts_with = "ipv6_only")]
+    pub ipv4_only: bool,
+    /// Only determine and publish the IPv6 address (AAAA records)
+    #[arg(long, global = true)]
+    pub ipv6_only: bool,
+    /// Store all HTTP requests and responses (without credentials) in this directory
+    #[arg(long, global = true, conflicts_with = "replay_http")]
+    pub record_http: Option<PathBuf>,
+    /// Answer all HTTP requests from a directory created with --record-http instead of the network
+    #[arg(long, global = true)]
+    pub replay_http: Option<PathBuf>,
+    /// List the zones again instead of using the cached IDs
+    #[arg(long, global = true)]
+    pub refresh_cache: bool,
+    /// Seconds to wait before the first check (overrides the configuration)
+    #[arg(long, global = true)]
+    pub startup_delay: Option<u64>,
+    /// Write a machine-readable report of each run to this file (JSON)
+    #[arg(long, global = true)]
+    pub report_file: Option<PathBuf>,
+    /// Format of the output
+    #[arg(short, long, value_enum, global = true, default_value_t = OutputMode::Human)]
+    pub output: OutputMode,
     /// Print app version
     #[arg(short, long)]
     pub version: bool,
+    /// Wait for another running instance to finish instead of skipping the run
+    #[arg(long, global = true, overrides_with = "no_wait")]
+    pub wait: bool,
+    /// Skip the run if another instance is running (default)
+    #[arg(long, global = true, overrides_with = "wait")]
+    pub no_wait: bool,
+}
+
+/// Options of a single run that are not part of the configuration
+#[derive(Default, Debug, Clone)]
+pub(crate) struct RunOptions {
+    pub create_missing: bool,
+    /// Address families of this run (the ones of the configuration if not set)
+    pub family: Option<Family>,
+    pub force: bool,
+    /// Addresses that are published instead of the detected ones
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+    /// Only check the records with these names (all records if not set)
+    pub records: Option<HashSet<String>>,
+    /// Discard the cached IDs before the check
+    pub refresh_cache: bool,
+    pub report_file: Option<PathBuf>,
+    /// Wait for another running instance instead of skipping the check
+    pub wait: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Measure the latency of the detection sources and the API
+    Bench {
+        /// Number of requests per source and API call
+        #[arg(short, long, default_value_t = 5)]
+        iterations: u32,
+    },
+    /// Run in the background and update the records periodically
+    Daemon {
+        /// Seconds to wait between checks (overrides the configuration)
+        #[arg(short, long)]
+        interval: Option<u64>,
+    },
+    /// Export the managed records
+    Export {
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Bind)]
+        format: ExportFormat,
+        /// Use the zone export of Cloudflare filtered to the managed records
+        #[arg(long)]
+        zone_export: bool,
+    },
+    /// Delete the records published by this tool whose names are no longer configured
+    Prune {
+        /// Only list the records that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Replace this executable with the latest release from GitHub
+    SelfUpdate {
+        /// Only check whether a newer release is available
+        #[arg(long)]
+        check: bool,
+    },
+    /// Install a service that runs this executable with the current configuration
+    Service {
+        #[command(subcommand)]
+        command: ServiceCommand,
+    },
+    /// Accept updates from routers via the DynDNS2 protocol (/nic/update) and apply them right away
+    ServeDyndns {
+        /// Address and port to listen on
+        #[arg(short, long, default_value = "0.0.0.0:8245")]
+        listen: SocketAddr,
+    },
+    /// Serve the IP address of the client ("what is my IP") to be used as detection source
+    ServeEcho {
+        /// Address and port to listen on
+        #[arg(short, long, default_value = "0.0.0.0:8080")]
+        listen: SocketAddr,
+        /// Header containing the client address set by a reverse proxy (e.g. X-Forwarded-For)
+        #[arg(long)]
+        proxy_header: Option<String>,
+        /// Address of a reverse proxy whose proxy header is trusted
+        #[arg(long, default_values = ["127.0.0.1", "::1"])]
+        trusted_proxy: Vec<IpAddr>,
+    },
+    /// Periodically update the records and show their state in a terminal UI
+    Watch {
+        /// Seconds to wait between checks
+        #[arg(short, long, default_value_t = 60)]
+        interval: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ServiceCommand {
+    /// Write a systemd unit and timer or a launchd plist, or register a scheduled task on Windows
+    Install {
+        /// Run the daemon permanently instead of periodic single runs
+        #[arg(long)]
+        daemon: bool,
+        /// Seconds between the periodic runs (default: the interval of the daemon)
+        #[arg(short, long)]
+        interval: Option<u64>,
+        /// Service manager to install for (default: the one of the current system)
+        #[arg(long, value_enum)]
+        manager: Option<ServiceManager>,
+        /// Print the files (or the command on Windows) instead of installing them
+        #[arg(long)]
+        print: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum ServiceManager {
+    /// macOS
+    Launchd,
+    /// Linux
+    Systemd,
+    /// Task Scheduler
+    Windows,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum ExportFormat {
+    /// Zone file syntax of BIND
+    Bind,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct RecordIds {
-    pub v4: Vec<String>,
-    pub v6: Vec<String>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputMode {
+    /// Plain lines of text
+    Human,
+    /// Workflow commands and job summary of GitHub Actions
+    Github,
 }
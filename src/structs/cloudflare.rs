@@ -4,27 +4,134 @@ use serde_json::Value as Json;
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Cloudflare {
     pub success: bool,
+    #[serde(default)]
+    pub errors: Vec<ApiMessage>,
+    #[serde(default)]
+    pub messages: Vec<ApiMessage>,
+    #[serde(default)]
     pub result: Json,
+    /// Paging of list endpoints
+    #[serde(default)]
+    pub result_info: Option<ResultInfo>,
+}
+
+/// Reason of a failure (e.g. 10000 for an authentication error) or an additional notice
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ApiMessage {
+    #[serde(default)]
+    pub code: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ResultInfo {
+    pub total_pages: Option<u32>,
 }
 
 pub(crate) mod request {
+    use chrono::{DateTime, Utc};
     use serde::{Deserialize, Serialize};
-    use std::net::IpAddr;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    /// Changes of several records of a zone that are applied at once (either all or none)
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct BatchDnsRecords {
+        pub patches: Vec<BatchPatch>,
+        pub puts: Vec<BatchPut>,
+    }
 
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct BatchPatch {
+        pub id: String,
+        #[serde(flatten)]
+        pub record: PatchDnsRecord,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct BatchPut {
+        pub id: String,
+        #[serde(flatten)]
+        pub record: PutDnsRecord,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct PatchCustomHostname {
+        pub custom_origin_server: String,
+    }
+
+    /// Fields that are None are left out, so that they keep their current value
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub(crate) struct PatchDnsRecord {
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub comment: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub content: Option<IpAddr>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub name: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub proxied: Option<bool>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub tags: Option<Vec<String>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         pub ttl: Option<u32>,
     }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct PatchSpectrumApp {
+        pub origin_direct: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct PutDnsRecord {
+        pub comment: Option<String>,
+        pub content: IpAddr,
+        pub name: String,
+        pub proxied: bool,
+        pub tags: Vec<String>,
+        pub ttl: u32,
+        #[serde(rename = "type")]
+        pub type_: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct PutFallbackOrigin {
+        pub origin: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct TxtRecord {
+        pub content: String,
+        pub name: String,
+        pub ttl: u32,
+        #[serde(rename = "type")]
+        pub type_: String,
+    }
+
+    /// Value of a Workers KV key written by the workers_kv action
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct PutWorkersKvValue {
+        pub ipv4: Option<Ipv4Addr>,
+        pub ipv6: Option<Ipv6Addr>,
+        pub updated_at: DateTime<Utc>,
+    }
 }
 
 pub(crate) mod response {
+    use chrono::{DateTime, Utc};
     use serde::{Deserialize, Serialize};
 
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct FallbackOrigin {
+        pub origin: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct ListCustomHostnames {
+        pub custom_origin_server: Option<String>,
+        pub hostname: String,
+        pub id: String,
+    }
+
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub(crate) struct ListZone {
         pub id: String,
@@ -33,10 +140,32 @@ pub(crate) mod response {
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub(crate) struct ListDnsRecords {
+        pub comment: Option<String>,
         pub content: String,
+        pub created_on: Option<DateTime<Utc>>,
         pub id: String,
         pub name: String,
+        #[serde(default)]
+        pub proxied: bool,
+        #[serde(default)]
+        pub tags: Vec<String>,
+        pub ttl: u32,
         #[serde(rename = "type")]
         pub type_: String,
     }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct VerifyToken {
+        pub expires_on: Option<DateTime<Utc>>,
+        pub id: String,
+        /// `active`, `disabled` or `expired`
+        pub status: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(crate) struct SpectrumApp {
+        pub id: String,
+        #[serde(default)]
+        pub origin_direct: Vec<String>,
+    }
 }
@@ -1,15 +1,790 @@
+use ipnet::Ipv6Net;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+};
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Config {
+    /// Further Cloudflare accounts with their own token, records and zones
+    #[serde(default)]
+    pub accounts: Vec<Account>,
+    #[serde(default)]
+    pub actions: Actions,
+    /// Email address of the user, required for the Global API Key
+    pub api_email: Option<String>,
+    /// Legacy Global API Key, used instead of the token if api_email is set as well
+    pub api_key: Option<String>,
+    /// Token of the main account, can be omitted if all records belong to further accounts
+    #[serde(default)]
     pub api_token: String,
+    /// Send the changes of all records of a zone in one request to the batch endpoint
+    #[serde(default)]
+    pub batch: bool,
+    /// Seconds the IDs of zones are reused from the state file instead of listing them again
+    /// (0 = disabled)
+    #[serde(default)]
+    pub cache_ttl: u64,
+    /// Create the A/AAAA records of names without a record of their type instead of skipping them
+    #[serde(default)]
+    pub create_missing: bool,
+    #[serde(default)]
+    pub daemon: Daemon,
+    /// Delete all but one of multiple records with the same name and type
+    #[serde(default)]
+    pub dedupe: bool,
+    #[serde(default)]
+    pub dedupe_keep: Keep,
+    #[serde(default)]
+    pub detection: Detection,
+    #[serde(default)]
+    pub dns: Dns,
+    #[serde(default)]
+    pub dyndns: Dyndns,
+    /// Report records that point at a Cloudflare Tunnel as errors instead of skipping them
+    #[serde(default)]
+    pub error_on_tunnel: bool,
+    /// Address families that are determined and published (--ipv4-only/--ipv6-only override it)
+    #[serde(default)]
+    pub family: Family,
+    /// Active/standby coordination of multiple hosts sharing this configuration
+    pub high_availability: Option<HighAvailability>,
+    /// Use HTTP/3 (QUIC) for API and cdn-cgi/trace requests, requires the http3 feature
+    #[serde(default)]
+    pub http3: bool,
     pub ipv6: Ipv6,
-    pub records: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub log: Log,
+    #[serde(default)]
+    pub maintenance: Maintenance,
+    /// Suppress further updates once this many have been made within an hour (unless forced)
+    pub max_updates_per_hour: Option<u32>,
+    #[serde(default)]
+    pub messages: Messages,
+    #[serde(default)]
+    pub ownership: Ownership,
+    /// Skip records that have been changed by someone else since they were last published (unless forced)
+    #[serde(default)]
+    pub protect_external_changes: bool,
+    /// API requests per 5 minutes and token (0 = unlimited), further requests wait until the budget
+    /// allows them
+    #[serde(default = "default_rate_limit")]
+    pub rate_limit: u32,
+    #[serde(default)]
+    pub records: Records,
+    #[serde(default)]
+    pub retry: Retry,
+    /// Skip the requests to Cloudflare while the detected addresses are unchanged, but check the
+    /// records at least once within this many seconds
+    pub refresh_interval: Option<u64>,
+    #[serde(default)]
+    pub spectrum: Spectrum,
+    /// Seconds to wait before the first check, e.g. for the WAN link after booting
+    #[serde(default)]
+    pub startup_delay: u64,
+    /// Location of the state file (default: inside the data directory)
+    pub state_file: Option<PathBuf>,
+    /// Additional uplinks with their own detection, records refer to them by name
+    #[serde(default)]
+    pub uplinks: HashMap<String, Uplink>,
+    /// Check the API token before the first request, account-owned tokens can't be verified
+    #[serde(default = "default_true")]
+    pub verify_token: bool,
+    /// Retry the detection for up to this many seconds before the first check (0 = disabled)
+    #[serde(default)]
+    pub wait_online: u64,
+    /// Settings that apply to all records of a zone
+    #[serde(default)]
+    pub zones: HashMap<String, ZoneSettings>,
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+fn default_rate_limit() -> u32 {
+    1200
+}
+
+/// Retries of API requests that failed with 429, a server error or a network error
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Retry {
+    /// Attempts per request including the first one (1 = no retries)
+    pub attempts: u32,
+    /// Milliseconds before the first retry, doubled with each further one
+    pub delay: u64,
+    /// Upper bound of the delay in milliseconds
+    pub max_delay: u64,
+}
+
+impl Retry {
+    pub(crate) const DEFAULT: Self = Self {
+        attempts: 3,
+        delay: 500,
+        max_delay: 10_000,
+    };
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct HighAvailability {
+    /// Identifier of this host in the heartbeat (default: the hostname)
+    pub node: Option<String>,
+    /// TXT record containing the heartbeat of the current leader
+    pub record: String,
+    /// Seconds without heartbeat after which a standby host takes over
+    #[serde(default = "default_high_availability_timeout")]
+    pub timeout: u64,
+}
+
+fn default_high_availability_timeout() -> u64 {
+    900
+}
+
+/// Templates replacing the default messages, with the placeholders {record}, {zone}, {type} and {ip}
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Messages {
+    /// Comment written with every update, additionally with the placeholders {timestamp} and
+    /// {hostname}
+    pub comment: Option<String>,
+    /// Record or zone that is skipped, {reason} contains the default message
+    pub skipped: Option<String>,
+    pub up_to_date: Option<String>,
+    pub updated: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Spectrum {
+    /// Spectrum applications whose direct origin points at the detected address
+    pub apps: Vec<SpectrumApp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SpectrumApp {
+    pub id: String,
+    /// Publish the IPv6 address instead of the IPv4 address
+    #[serde(default)]
+    pub ipv6: bool,
+    /// Port of the origin
+    pub port: u16,
+    #[serde(default)]
+    pub protocol: Protocol,
+    /// Publish the addresses of this uplink instead of the ones of the detection section
+    pub uplink: Option<String>,
+    pub zone: String,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Uplink {
+    /// Network interface the detection requests are sent from (Linux only)
+    pub interface: Option<String>,
+    /// Local address the IPv4 detection requests are sent from
+    pub local_ipv4: Option<Ipv4Addr>,
+    /// Local address the IPv6 detection requests are sent from
+    pub local_ipv6: Option<Ipv6Addr>,
+    /// Detection sources of this uplink (default: the sources of the detection section)
+    #[serde(default)]
+    pub sources: Vec<Source>,
+}
+
+/// Account that is processed with the remaining settings of the configuration in the same run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Account {
+    pub api_email: Option<String>,
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub api_token: String,
+    #[serde(default)]
+    pub records: Records,
+    #[serde(default)]
+    pub zones: HashMap<String, ZoneSettings>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ZoneSettings {
+    /// Custom origin servers of Cloudflare for SaaS custom hostnames, keyed by custom hostname
+    #[serde(default)]
+    pub custom_origins: HashMap<String, String>,
+    /// Hostname that Cloudflare for SaaS uses as fallback origin of the zone
+    pub fallback_origin: Option<String>,
+    /// ID of the zone, the zones are not listed if all records are in zones with an ID
+    pub id: Option<String>,
+    /// Seconds between checks in daemon mode (overrides the daemon interval)
+    pub interval: Option<u64>,
+    /// Fixed IPv4 address of the A records that bypasses the detection
+    #[serde(alias = "ip")]
+    pub ipv4: Option<Ipv4Addr>,
+    /// Fixed IPv6 address of the AAAA records that bypasses the detection
+    #[serde(alias = "ip6")]
+    pub ipv6: Option<Ipv6Addr>,
+    /// Whether the records are proxied (overridden by the setting of a record)
+    pub proxied: Option<bool>,
+    /// Tags of the records (overridden by the ones of a record)
+    pub tags: Option<Vec<String>>,
+    /// TTL of the records (overridden by the one of a record)
+    pub ttl: Option<u32>,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Keep {
+    #[default]
+    Oldest,
+    Newest,
+    /// The record that already has the desired address (the oldest one if none has it), so that the
+    /// remaining record does not have to be updated
+    Matching,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Actions {
+    /// Shell command that is run whenever a record switches to its failover fallback or back,
+    /// DDNS_RECORD and DDNS_FAILOVER ("active" or "recovered") are set
+    pub failover: Option<String>,
+    pub templates: Vec<TemplateAction>,
+    pub workers_kv: Vec<WorkersKvAction>,
+}
+
+/// Renders a file containing `{ipv4}`/`{ipv6}` placeholders whenever the result changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TemplateAction {
+    /// Shell command that is run after the destination has been rewritten
+    pub command: Option<String>,
+    pub destination: PathBuf,
+    pub template: PathBuf,
+}
+
+/// Writes the detected addresses to a key of a Workers KV namespace whenever they change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct WorkersKvAction {
+    pub account_id: String,
+    pub key: String,
+    pub namespace_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Records {
+    /// Fully qualified names, the owning zone is determined automatically
+    Names(Vec<RecordEntry>),
+    /// Names relative to the zone they belong to ("@" for the zone apex)
+    Zones(HashMap<String, Vec<RecordEntry>>),
+}
+
+impl Default for Records {
+    fn default() -> Self {
+        Self::Zones(HashMap::new())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum RecordEntry {
+    Name(String),
+    Detailed {
+        name: String,
+        #[serde(flatten)]
+        settings: Box<RecordSettings>,
+    },
+}
+
+impl RecordEntry {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Self::Name(name) => name,
+            Self::Detailed { name, .. } => name,
+        }
+    }
+
+    pub(crate) fn settings(&self) -> RecordSettings {
+        match self {
+            Self::Name(_) => RecordSettings::default(),
+            Self::Detailed { settings, .. } => settings.as_ref().to_owned(),
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RecordSettings {
+    pub comment: Option<String>,
+    /// Minimum seconds between two updates, changes within are deferred
+    pub cooldown: Option<u64>,
+    /// Content that is published while the detection of the record fails
+    pub failover: Option<Failover>,
+    /// Seconds between checks in daemon mode (overrides the zone and daemon interval)
+    pub interval: Option<u64>,
+    /// Fixed IPv4 address that bypasses the detection (overrides the one of the zone)
+    #[serde(alias = "ip")]
+    pub ipv4: Option<Ipv4Addr>,
+    /// Fixed IPv6 address that bypasses the detection (overrides the one of the zone)
+    #[serde(alias = "ip6")]
+    pub ipv6: Option<Ipv6Addr>,
+    #[serde(default)]
+    pub mode: Mode,
+    /// Length of the detected prefix that is kept when combining it with the suffix (default: 64)
+    pub prefix_length: Option<u8>,
+    /// Whether the record is proxied, enforced in both modes (overrides the setting of the zone)
+    pub proxied: Option<bool>,
+    /// Interface identifier that replaces the one of the detected IPv6 address
+    pub suffix: Option<Ipv6Addr>,
+    /// Tags (`name:value`) that are enforced in both modes, an empty list removes all tags
+    /// (overrides the ones of the zone)
+    pub tags: Option<Vec<String>>,
+    /// TTL that is enforced in both modes (overrides the one of the zone, 1 = automatic)
+    pub ttl: Option<u32>,
+    /// Publish the addresses of this uplink instead of the ones of the detection section
+    pub uplink: Option<String>,
+    /// Publish one record per address of these uplinks (multihomed hosts), replaces `uplink`
+    #[serde(default)]
+    pub uplinks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Failover {
+    /// Consecutive checks without any detected address before the fallback is published
+    #[serde(default = "default_failover_after")]
+    pub after: u32,
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+    /// Publish the addresses of this uplink instead of fixed addresses
+    pub uplink: Option<String>,
+}
+
+fn default_failover_after() -> u32 {
+    3
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Mode {
+    /// Only change the content of the record
+    #[default]
+    Patch,
+    /// Overwrite the whole record with the configured values
+    Replace,
+}
+
+/// A configured record after its zone has been resolved
+#[derive(Debug, Clone)]
+pub(crate) struct Target {
+    pub name: String,
+    pub settings: RecordSettings,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Detection {
+    /// Accept addresses that are not globally routable (e.g. 192.168.0.0/16 or fd00::/8)
+    pub allow_private: bool,
+    /// Query all sources and only accept an address that at least this many of them returned
+    pub consensus: Option<usize>,
+    /// Look up the network of the detected addresses in local MMDB files
+    pub geoip: Option<Geoip>,
+    /// Don't publish IPv4 addresses of a carrier-grade NAT (100.64.0.0/10)
+    pub skip_on_cgnat: bool,
+    /// Sources that are queried in order until one of them returns an address
+    pub sources: Vec<Source>,
+    /// Seconds after which a source that has not answered is considered failed
+    pub timeout: Option<u64>,
+    /// Seconds after which the detection of a family is given up, both are determined concurrently
+    pub total_timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Geoip {
+    /// Autonomous systems the addresses are expected in (any if empty)
+    #[serde(default)]
+    pub asns: Vec<u32>,
+    /// ISO codes of the countries the addresses are expected in (any if empty)
+    #[serde(default)]
+    pub countries: Vec<String>,
+    /// MMDB files with ASN and/or country data (e.g. GeoLite2-ASN.mmdb and GeoLite2-Country.mmdb)
+    pub databases: Vec<PathBuf>,
+    /// Don't publish addresses of unexpected networks instead of only warning about them
+    #[serde(default)]
+    pub reject: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Source {
+    /// Shell command printing the addresses, DDNS_FAMILY is set to "ipv4" or "ipv6"
+    Command(String),
+    /// Names of DNS servers that answer with the address of the client
+    Dns(DnsSource),
+    /// File or FIFO containing the addresses, "-" reads them from standard input
+    File(PathBuf),
+    /// WAN addresses of an AVM Fritz!Box queried via TR-064
+    Fritzbox(FritzboxSource),
+    Http(HttpSource),
+    /// Global address of a local network interface (e.g. "eth0") for hosts with public addresses
+    Interface(String),
+    /// Address of an interface or address list of a MikroTik router queried via its REST API
+    Mikrotik(MikrotikSource),
+    /// External IPv4 address of the gateway queried with NAT-PMP, or PCP if it does not support NAT-PMP
+    Natpmp(GatewaySource),
+    /// Fixed addresses, e.g. of an uplink with static addresses
+    Static(Vec<IpAddr>),
+    /// STUN server (host and port, e.g. "stun.l.google.com:19302") answering binding requests
+    Stun(String),
+    /// cdn-cgi/trace of 1.1.1.1 and 2606:4700:4700::1111, which determines the address family
+    Trace,
+    /// Status of the network interfaces of OpenWrt (when running on the router itself)
+    Ubus(UbusSource),
+    /// External IPv4 address of the Internet Gateway Device (router) in the local network
+    Upnp,
+}
+
+impl Source {
+    /// Whether the source reports the outgoing address as seen from the outside, instead of an
+    /// address known to the router, a command, a file, an interface or the configuration
+    pub(crate) fn is_outgoing(&self) -> bool {
+        match self {
+            Self::Dns(_) | Self::Http(_) | Self::Stun(_) | Self::Trace => true,
+            Self::Command(_) | Self::File(_) | Self::Fritzbox(_) | Self::Interface(_) => false,
+            Self::Mikrotik(_) | Self::Natpmp(_) | Self::Static(_) | Self::Ubus(_) => false,
+            Self::Upnp => false,
+        }
+    }
+
+    /// Describes the source in messages and tables
+    pub(crate) fn label(&self) -> String {
+        match self {
+            Self::Command(x) => format!("command {x}"),
+            Self::Dns(DnsSource::Google) => String::from("DNS o-o.myaddr.l.google.com"),
+            Self::Dns(DnsSource::Opendns) => String::from("DNS myip.opendns.com"),
+            Self::File(x) => x.display().to_string(),
+            Self::Fritzbox(x) => format!("Fritz!Box {}", x.host),
+            Self::Http(x) => x.url.to_owned(),
+            Self::Interface(x) => format!("interface {x}"),
+            Self::Mikrotik(x) => format!("MikroTik {}", x.url),
+            Self::Natpmp(x) => match x.gateway {
+                Some(gateway) => format!("NAT-PMP/PCP {gateway}"),
+                None => String::from("NAT-PMP/PCP default gateway"),
+            },
+            Self::Static(x) => format!(
+                "static {}",
+                x.iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Stun(x) => format!("STUN {x}"),
+            Self::Ubus(x) => format!("ubus {}", x.interfaces.join(", ")),
+            Self::Upnp => String::from("UPnP Internet Gateway Device"),
+            Self::Trace => String::from("cdn-cgi/trace (1.1.1.1, 2606:4700:4700::1111)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DnsSource {
+    /// TXT record o-o.myaddr.l.google.com of ns1.google.com
+    Google,
+    /// A/AAAA record myip.opendns.com of resolver1.opendns.com
+    Opendns,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct FritzboxSource {
+    pub host: String,
+    pub password: Option<String>,
+    /// Port of TR-064 (HTTP)
+    pub port: u16,
+    /// User of the Fritz!Box, only needed if the box requires a login for TR-064
+    pub username: Option<String>,
+}
+
+impl Default for FritzboxSource {
+    fn default() -> Self {
+        Self {
+            host: String::from("fritz.box"),
+            password: None,
+            port: 49000,
+            username: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct MikrotikSource {
+    /// Firewall address list containing the address (alternative to the interface)
+    pub address_list: Option<String>,
+    /// Accept self-signed certificates of the router
+    #[serde(default)]
+    pub insecure: bool,
+    pub interface: Option<String>,
+    pub password: Option<String>,
+    /// Base URL of the router, e.g. "https://192.168.88.1"
+    pub url: String,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct UbusSource {
+    /// Logical interfaces that are asked in order
+    pub interfaces: Vec<String>,
+}
+
+impl Default for UbusSource {
+    fn default() -> Self {
+        Self {
+            interfaces: vec![String::from("wan"), String::from("wan6")],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct GatewaySource {
+    /// Address of the gateway (default: the default gateway, only determined on Linux)
+    pub gateway: Option<Ipv4Addr>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct HttpSource {
+    pub basic_auth: Option<BasicAuth>,
+    #[serde(default)]
+    pub format: ResponseFormat,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub url: String,
+}
+
+/// How the address is extracted from the response of an HTTP source
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) enum ResponseFormat {
+    /// Only the address or the key-value format of cdn-cgi/trace (`ip=...`)
+    #[default]
+    Text,
+    /// Field of a JSON object, nested fields and array elements are separated by dots (`json:data.ip`)
+    Json(String),
+    /// First capture group (or the whole match) of a regular expression (`regex:<pattern>`)
+    Regex(Regex),
+}
+
+impl TryFrom<String> for ResponseFormat {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.split_once(':') {
+            Some(("json", path)) => Ok(Self::Json(path.to_owned())),
+            Some(("regex", pattern)) => Regex::new(pattern)
+                .map(Self::Regex)
+                .map_err(|e| e.to_string()),
+            _ if value == "text" => Ok(Self::Text),
+            _ => Err(format!(
+                "unknown format \"{value}\", expected \"text\", \"json:<path>\" or \"regex:<pattern>\""
+            )),
+        }
+    }
+}
+
+impl From<ResponseFormat> for String {
+    fn from(value: ResponseFormat) -> Self {
+        match value {
+            ResponseFormat::Text => String::from("text"),
+            ResponseFormat::Json(path) => format!("json:{path}"),
+            ResponseFormat::Regex(regex) => format!("regex:{}", regex.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BasicAuth {
+    pub password: Option<String>,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Daemon {
+    /// Groups whose members may use the control socket
+    pub allowed_gids: Vec<u32>,
+    /// Users that may use the control socket (in addition to root and the owner of the daemon)
+    pub allowed_uids: Vec<u32>,
+    /// Interfaces whose address changes trigger a check (default: all)
+    pub event_interfaces: Vec<String>,
+    /// Check immediately when a global address is added or removed instead of waiting for the interval
+    pub events: bool,
+    /// Seconds to wait between checks
+    pub interval: u64,
+    /// Random deviation from the interval in percent, spreads the checks of many instances (not
+    /// applied to a schedule)
+    pub jitter: u8,
+    /// Cron expression of the checks, replaces the interval (the first check waits for it as well)
+    pub schedule: Option<String>,
+    /// Unix domain socket accepting control commands (sync, status, reload)
+    pub socket: Option<PathBuf>,
+    /// Timezone of the schedule
+    pub timezone: Timezone,
+    /// Reload the configuration whenever its file changes
+    pub watch_config: bool,
+}
+
+impl Default for Daemon {
+    fn default() -> Self {
+        Self {
+            allowed_gids: Vec::new(),
+            allowed_uids: Vec::new(),
+            event_interfaces: Vec::new(),
+            events: false,
+            interval: 300,
+            jitter: 0,
+            schedule: None,
+            socket: None,
+            timezone: Timezone::Local,
+            watch_config: false,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Dns {
+    pub resolver: Resolver,
+    /// Query the resolver after updating records to check that they resolve to the new content
+    pub verify: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Resolver {
+    /// DNS-over-HTTPS (RFC 8484)
+    Https(String),
+    /// DNS-over-TLS (RFC 7858)
+    Tls(TlsResolver),
+    /// Plain DNS over UDP
+    Udp(SocketAddr),
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::Udp(SocketAddr::from((Ipv4Addr::new(1, 1, 1, 1), 53)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TlsResolver {
+    pub address: SocketAddr,
+    /// Name that the certificate of the resolver is validated against
+    pub server_name: String,
+}
+
+/// Credentials that routers have to send to the DynDNS server (serve-dyndns)
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Dyndns {
+    pub password: Option<String>,
+    pub username: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Ipv6 {
+    #[serde(default)]
+    pub npt: Vec<Npt>,
     pub prefer_eui64: bool,
     pub prefer_outgoing: bool,
+    /// Skip temporary (privacy extensions) and deprecated addresses if there are others (Linux only)
+    #[serde(default = "default_true")]
+    pub prefer_stable: bool,
+}
+
+/// Network prefix translation (NPTv6) applied to the addresses of the network interfaces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Npt {
+    #[serde(default = "default_true")]
+    pub checksum_neutral: bool,
+    pub external: Ipv6Net,
+    pub internal: Ipv6Net,
+}
+
+/// Marker in the comment of the records that are managed by this tool (like the TXT registry of
+/// external-dns), records without it are never deleted
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Ownership {
+    /// Skip records without the marker instead of adding it to them
+    pub managed_only: bool,
+    /// e.g. `managed-by=cloudflare-ddns`
+    pub marker: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Maintenance {
+    pub timezone: Timezone,
+    /// Cron expressions matching the minutes during which changes may be applied (always if empty)
+    pub windows: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Log {
+    pub file: Option<PathBuf>,
+    pub timestamp_format: String,
+    pub timestamps: bool,
+    pub timezone: Timezone,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            file: None,
+            timestamp_format: String::from("%Y-%m-%dT%H:%M:%S%:z"),
+            timestamps: false,
+            timezone: Timezone::Local,
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Family {
+    #[default]
+    Both,
+    Ipv4,
+    Ipv6,
+}
+
+impl Family {
+    pub(crate) fn has_ipv4(self) -> bool {
+        self != Self::Ipv6
+    }
+
+    pub(crate) fn has_ipv6(self) -> bool {
+        self != Self::Ipv4
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Timezone {
+    #[default]
+    Local,
+    Utc,
+}
+
+fn default_true() -> bool {
+    true
 }
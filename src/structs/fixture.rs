@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// A recorded HTTP exchange, request headers (including credentials) are not stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Fixture {
+    /// Response body if it is valid UTF-8
+    pub body: Option<String>,
+    /// Hex encoded response body otherwise (e.g. DNS messages)
+    pub body_hex: Option<String>,
+    pub content_type: Option<String>,
+    pub method: String,
+    pub request_body: Option<String>,
+    pub status: u16,
+    /// URL without the user information
+    pub url: String,
+}
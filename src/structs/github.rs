@@ -0,0 +1,15 @@
+pub(crate) mod response {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub(crate) struct Release {
+        pub assets: Vec<Asset>,
+        pub tag_name: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub(crate) struct Asset {
+        pub browser_download_url: String,
+        pub name: String,
+    }
+}
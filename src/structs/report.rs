@@ -0,0 +1,117 @@
+use crate::i18n::t;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct Report {
+    pub duration_ms: u64,
+    pub errors: Vec<String>,
+    pub finished_at: DateTime<Utc>,
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+    pub records: Vec<Record>,
+    pub started_at: DateTime<Utc>,
+    /// Addresses of the configured uplinks that are used by records
+    pub uplinks: BTreeMap<String, Addresses>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Addresses {
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Record {
+    pub action: Action,
+    pub content: String,
+    pub desired: Option<IpAddr>,
+    pub duration_ms: u64,
+    /// The content differs from the one that was last published by this tool
+    pub external_change: bool,
+    /// The fallback content of the failover settings is published
+    pub failover: bool,
+    pub name: String,
+    pub proxied: bool,
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Whether the record resolved to the new content after the update (if verification is enabled)
+    pub verified: Option<bool>,
+    pub zone: String,
+}
+
+impl Record {
+    pub(crate) fn status(&self) -> String {
+        match self.external_change {
+            true => t!("status-changed-externally", action = self.action),
+            false => self.action.to_string(),
+        }
+    }
+
+    pub(crate) fn not_found(zone: &str, name: &str) -> Self {
+        Self {
+            action: Action::NotFound,
+            content: String::new(),
+            desired: None,
+            duration_ms: 0,
+            external_change: false,
+            failover: false,
+            name: name.to_owned(),
+            proxied: false,
+            type_: String::new(),
+            verified: None,
+            zone: zone.to_owned(),
+        }
+    }
+
+    pub(crate) fn tunnel(zone: &str, name: &str, content: &str) -> Self {
+        Self {
+            action: Action::Tunnel,
+            content: content.to_owned(),
+            type_: String::from("CNAME"),
+            ..Self::not_found(zone, name)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Action {
+    /// Added as further record of a name with several addresses
+    Created,
+    /// Updated recently, the change is applied after the cooldown
+    Deferred,
+    Deleted,
+    Failed,
+    NotFound,
+    /// Outside of the maintenance windows, the change is applied later
+    Queued,
+    Skipped,
+    /// Points at a Cloudflare Tunnel, which does not use the address
+    Tunnel,
+    UpToDate,
+    Updated,
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let id = match self {
+            Self::Created => "action-created",
+            Self::Deferred => "action-deferred",
+            Self::Deleted => "action-deleted",
+            Self::Failed => "action-failed",
+            Self::NotFound => "action-not-found",
+            Self::Queued => "action-queued",
+            Self::Skipped => "action-skipped",
+            Self::Tunnel => "action-tunnel",
+            Self::UpToDate => "action-up-to-date",
+            Self::Updated => "action-updated",
+        };
+        f.write_str(&t!(id))
+    }
+}
@@ -0,0 +1,59 @@
+use super::report::Addresses;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct State {
+    /// IDs of zones and records that are reused within cache_ttl instead of listing them again
+    pub cache: Cache,
+    /// Addresses of the last complete check without errors (for refresh_interval)
+    pub detected: Option<Detected>,
+    /// Consecutive checks without any detected address, keyed by the name of records with failover
+    pub failures: HashMap<String, u32>,
+    /// Delegated prefix of the detected IPv6 address with the longest configured prefix length, a
+    /// change renumbers the records with a suffix
+    pub prefix: Option<Ipv6Addr>,
+    /// Last content published by this tool, keyed by record ID
+    pub records: HashMap<String, Published>,
+    /// Times of the updates during the last hour (for max_updates_per_hour)
+    pub updates: Vec<DateTime<Utc>>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Cache {
+    /// All zones of the tokens as they were listed, keyed by account and name (`<account>/<name>`),
+    /// so that an account never uses the zones of another one
+    pub zones: HashMap<String, CachedZone>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CachedZone {
+    pub cached_at: DateTime<Utc>,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Detected {
+    pub checked_at: DateTime<Utc>,
+    /// Hash of the configuration, the records are checked again if it changes
+    pub configuration: String,
+    pub ipv4: Option<Ipv4Addr>,
+    pub ipv6: Option<Ipv6Addr>,
+    #[serde(default)]
+    pub uplinks: BTreeMap<String, Addresses>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Published {
+    pub content: String,
+    pub name: String,
+    pub published_at: DateTime<Utc>,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
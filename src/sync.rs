@@ -0,0 +1,1761 @@
+use crate::{
+    actions,
+    api::{
+        api_delete, api_get_all, api_patch, api_post, api_put, client, deserialize_json_value,
+        deserialize_response, Auth, RECORDS_PER_PAGE, ZONES_PER_PAGE,
+    },
+    config,
+    dns::{self, RecordType},
+    errors::{handle_errors, message, ErrorKind, Failure},
+    geoip, ha,
+    i18n::t,
+    ip, maintenance, output, saas, shutdown, spectrum, state,
+    structs::{
+        cloudflare::request::{
+            BatchDnsRecords, BatchPatch, BatchPut, PatchDnsRecord, PutDnsRecord,
+        },
+        cloudflare::response::{ListDnsRecords, ListZone},
+        config::{Config, Failover, Keep, Mode, Target},
+        report::{Action, Addresses, Record, Report},
+        state::{Cache, CachedZone, Detected, Published, State},
+        RunOptions,
+    },
+};
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use reqwest::{Client as HttpClient, Response, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    io::Error as IOError,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+const VERIFY_ATTEMPTS: u32 = 3;
+const VERIFY_DELAY: Duration = Duration::from_secs(5);
+
+/// Updates all records and writes the report file if requested
+pub(crate) async fn run(config: &Config, options: &RunOptions) -> Result<Report, Failure> {
+    let started_at = Utc::now();
+    let timer = Instant::now();
+    output::take_errors();
+
+    let mut report = Report::default();
+    let result = update_records(config, options, &mut report).await;
+
+    report.errors = output::take_errors();
+    if let Err(e) = &result {
+        report.errors.push(message(&e.kind));
+    }
+    report.started_at = started_at;
+    report.finished_at = Utc::now();
+    report.duration_ms = elapsed_ms(timer);
+
+    if let Some(path) = &options.report_file {
+        let result = serde_json::to_string_pretty(&report)
+            .map_err(IOError::other)
+            .and_then(|x| fs::write(path, x));
+        if let Err(e) = result {
+            handle_errors(&ErrorKind::Report(e));
+        }
+    }
+
+    result.map(|_| report)
+}
+
+async fn update_records(
+    config: &Config,
+    options: &RunOptions,
+    report: &mut Report,
+) -> Result<(), Failure> {
+    let family = options.family.unwrap_or(config.family);
+    let state_path = match state::path(config) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            handle_errors(&ErrorKind::State(e));
+            None
+        }
+    };
+
+    let mut state = match &state_path {
+        Some(path) => state::load(path).unwrap_or_else(|e| {
+            handle_errors(&ErrorKind::State(e));
+            State::default()
+        }),
+        None => State::default(),
+    };
+
+    // Forced runs apply changes regardless of the maintenance windows
+    let window_open = options.force || maintenance::is_open(&config.maintenance);
+
+    let configured = config::all_records(config);
+
+    // The addresses of the detection section are not needed if all records and applications use an
+    // uplink or fixed addresses
+    let uses_detection = config::accounts(config).iter().any(|account| {
+        config::resolve_records(&account.records, &[])
+            .into_values()
+            .flatten()
+            .any(|x| {
+                let (ipv4, ipv6) = config::fixed_addresses(account, &x);
+                x.settings.uplink.is_none()
+                    && x.settings.uplinks.is_empty()
+                    && ((family.has_ipv4() && ipv4.is_none())
+                        || (family.has_ipv6() && ipv6.is_none()))
+            })
+    }) || config.spectrum.apps.iter().any(|x| x.uplink.is_none());
+    let is_provided = options.ipv4.is_some() || options.ipv6.is_some();
+    if is_provided {
+        report.ipv4 = options.ipv4.filter(|_| family.has_ipv4());
+        report.ipv6 = options.ipv6.filter(|_| family.has_ipv6());
+    } else if config.uplinks.is_empty() || uses_detection {
+        let (ipv4, ipv6) = ip::determine_ip(config, family).await;
+        let mut ipv4 = ipv4.filter(|x| geoip::check(config, IpAddr::V4(*x)));
+        let ipv6 = ipv6.filter(|x| geoip::check(config, IpAddr::V6(*x)));
+        let is_cgnat = ipv4.is_some_and(|x| ip::skip_cgnat(config, x));
+        if is_cgnat {
+            ipv4 = None;
+        }
+        report.ipv4 = ipv4;
+        report.ipv6 = ipv6;
+
+        if ipv4.is_none() && family.has_ipv4() && !is_cgnat {
+            handle_errors(&ErrorKind::IPv4)
+        };
+
+        if ipv6.is_none() && family.has_ipv6() {
+            handle_errors(&ErrorKind::IPv6)
+        };
+
+        // Records with failover settings still have to be processed to publish their fallback
+        let has_failover = configured
+            .iter()
+            .any(|x| x.settings.uplink.is_none() && x.settings.failover.is_some());
+        if ipv4.is_none() && ipv6.is_none() && !has_failover && !is_cgnat {
+            return Err(Failure::new(ErrorKind::NoIp, 102));
+        }
+    }
+
+    for (name, uplink) in &config.uplinks {
+        let is_used = configured.iter().any(|x| {
+            x.settings.uplink.as_ref() == Some(name)
+                || x.settings.uplinks.contains(name)
+                || x.settings.failover.as_ref().and_then(|x| x.uplink.as_ref()) == Some(name)
+        }) || config
+            .spectrum
+            .apps
+            .iter()
+            .any(|x| x.uplink.as_ref() == Some(name));
+        if !is_used {
+            continue;
+        }
+
+        let (ipv4, ipv6) = ip::determine_uplink(config, uplink).await;
+        let ipv4 = ipv4.filter(|x| {
+            family.has_ipv4() && !ip::skip_cgnat(config, *x) && geoip::check(config, IpAddr::V4(*x))
+        });
+        let ipv6 = ipv6.filter(|x| family.has_ipv6() && geoip::check(config, IpAddr::V6(*x)));
+        if ipv4.is_none() && ipv6.is_none() {
+            output::error(t!("uplink-no-ip", uplink = name));
+        }
+        report
+            .uplinks
+            .insert(name.to_owned(), Addresses { ipv4, ipv6 });
+    }
+
+    // Records composed from the prefix and a suffix (e.g. other hosts of the network) are refreshed
+    // right away when the prefix changes, regardless of their cooldown and the update limit. The
+    // prefix is kept with the longest configured length so that it covers the shorter ones as well
+    let prefix_length = configured
+        .iter()
+        .filter(|x| x.settings.suffix.is_some())
+        .map(|x| x.settings.prefix_length.unwrap_or(64))
+        .max()
+        .unwrap_or(64);
+    let prefix = report
+        .ipv6
+        .map(|x| ip::with_suffix(x, Ipv6Addr::UNSPECIFIED, prefix_length));
+    let renumbered = match (state.prefix, prefix) {
+        (Some(previous), Some(current)) if previous != current => {
+            output::info(t!("prefix-changed", previous = previous, current = current));
+            Some((previous, current))
+        }
+        _ => None,
+    };
+
+    let fingerprint = fingerprint(config);
+    // The heartbeat of high availability has to be refreshed with every check, otherwise a standby
+    // host takes over while the addresses are unchanged
+    let is_skippable = config.high_availability.is_none();
+    if let (Some(refresh_interval), Some(detected), true) =
+        (config.refresh_interval, &state.detected, is_skippable)
+    {
+        let is_unchanged = detected.configuration == fingerprint
+            && detected.ipv4 == report.ipv4
+            && detected.ipv6 == report.ipv6
+            && detected.uplinks == report.uplinks;
+        let is_due = Utc::now() - detected.checked_at
+            >= TimeDelta::seconds(i64::try_from(refresh_interval).unwrap_or(i64::MAX));
+        if is_unchanged && !is_due && !options.force && options.records.is_none() {
+            output::info(t!("addresses-unchanged", checked_at = detected.checked_at));
+            return Ok(());
+        }
+    }
+
+    let http: HttpClient =
+        client(config).map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 133))?;
+
+    let api_base: Url = Url::parse("https://api.cloudflare.com/client/v4/")
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 103))?;
+
+    if options.refresh_cache || config.cache_ttl == 0 {
+        state.cache = Cache::default();
+    }
+
+    // The accounts are processed one after another with the addresses detected above
+    let mut failure = None;
+    for account in config::accounts(config) {
+        if shutdown::requested() {
+            break;
+        }
+        let config = &account;
+        let account_id = Auth::of(config).id();
+
+        let mut data_zones = config::configured_zones(config);
+        // A failure ends the run after the changes made so far have been recorded
+        let result = async {
+            // Names of the zones whose ID is taken from the cache
+            let mut cached_zones = HashSet::new();
+            for (key, cached) in &state.cache.zones {
+                let Some(name) = key.strip_prefix(&format!("{account_id}/")) else {
+                    continue;
+                };
+                if is_cached(config, cached.cached_at) && !data_zones.iter().any(|x| x.name == name)
+                {
+                    data_zones.push(ListZone {
+                        id: cached.id.to_owned(),
+                        name: name.to_owned(),
+                    });
+                    cached_zones.insert(name.to_owned());
+                }
+            }
+            if config::needs_zone_listing(config, &data_zones) {
+                let listed = list_zones(&http, &api_base, config, &account_id, &mut state).await?;
+                // Configured IDs take precedence over the listed ones, cached IDs don't
+                for zone in listed {
+                    match data_zones.iter_mut().find(|x| x.name == zone.name) {
+                        Some(x) if cached_zones.remove(&zone.name) => *x = zone,
+                        Some(_) => {}
+                        None => data_zones.push(zone),
+                    }
+                }
+            }
+
+            if let Some(settings) = &config.high_availability {
+                // A standby host only skips publishing, the state is still saved below
+                if !ha::is_leader(&http, &api_base, config, settings, &data_zones).await? {
+                    return Ok(());
+                }
+            }
+
+            let zone_names = data_zones
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>();
+            let mut resolved = config::resolve_records(&config.records, &zone_names);
+            if let Some(names) = &options.records {
+                for targets in resolved.values_mut() {
+                    targets.retain(|x| names.contains(&x.name));
+                }
+                resolved.retain(|_, targets| !targets.is_empty());
+            }
+
+            'zones: for (config_zone, targets) in &resolved {
+                let mut zone = match obtain_zone(&data_zones, config_zone).await {
+                    Some(x) => x,
+                    None => {
+                        output::info(custom_message(
+                            config.messages.skipped.as_ref(),
+                            t!("zone-not-found", zone = config_zone),
+                            &[
+                                ("record", ""),
+                                ("zone", config_zone),
+                                ("type", ""),
+                                ("ip", ""),
+                            ],
+                        ));
+                        report
+                            .records
+                            .push(Record::not_found(config_zone, config_zone));
+                        continue;
+                    }
+                };
+
+                // A target is processed again if the cached ID of its zone turns out to be stale
+                let mut queue = targets.iter().collect::<VecDeque<_>>();
+                let mut pending = Vec::new();
+                'targets: loop {
+                    // Records that have not been started yet are left for the next run
+                    if shutdown::requested() {
+                        queue.clear();
+                    }
+                    // The batched changes are sent once all targets of the zone have been processed
+                    let Some(target) = queue.pop_front() else {
+                        if !pending.is_empty() {
+                            update_batch(
+                                &http, &api_base, config, &zone, pending, &mut state, report,
+                            )
+                            .await?;
+                        }
+                        break 'targets;
+                    };
+
+                    let record_name = target.name.as_str();
+
+                    // Only the zone IDs are cached, the records are listed with every check so that
+                    // changes made by someone else are noticed right away
+                    let data_records =
+                        match list_records(&http, &api_base, config, &zone, record_name).await? {
+                            Ok(x) => x,
+                            Err(e) => {
+                                // The zone may have been deleted and added again with a new ID, the
+                                // zones are listed again and the target is retried once
+                                if cached_zones.remove(&zone.name) {
+                                    output::info(t!("cache-stale-zone", zone = zone.name));
+                                    state
+                                        .cache
+                                        .zones
+                                        .remove(&format!("{account_id}/{}", zone.name));
+                                    let listed = list_zones(
+                                        &http,
+                                        &api_base,
+                                        config,
+                                        &account_id,
+                                        &mut state,
+                                    )
+                                    .await?;
+                                    if let Some(x) = obtain_zone(&listed, &zone.name).await {
+                                        zone = x;
+                                        queue.push_front(target);
+                                        continue 'targets;
+                                    }
+                                }
+                                handle_errors(&e);
+                                report.records.push(Record {
+                                    action: Action::Failed,
+                                    ..Record::not_found(&zone.name, record_name)
+                                });
+                                continue;
+                            }
+                        };
+
+                    let (ipv4, ipv6) = match &target.settings.uplink {
+                        Some(uplink) => match report.uplinks.get(uplink) {
+                            Some(x) => (x.ipv4, x.ipv6),
+                            None => {
+                                output::error(t!(
+                                    "uplink-unknown",
+                                    uplink = uplink,
+                                    name = record_name
+                                ));
+                                continue;
+                            }
+                        },
+                        None => (report.ipv4, report.ipv6),
+                    };
+                    let (fixed_ipv4, fixed_ipv6) = config::fixed_addresses(config, target);
+                    let (ipv4, ipv6) = (fixed_ipv4.or(ipv4), fixed_ipv6.or(ipv6));
+
+                    let failover = match &target.settings.failover {
+                        Some(x) => {
+                            let failed = ipv4.is_none() && ipv6.is_none();
+                            is_failed_over(config, &mut state, record_name, x, failed).await
+                        }
+                        None => false,
+                    };
+                    let (ipv4, ipv6) = match (failover, &target.settings.failover) {
+                        (true, Some(x)) => match &x.uplink {
+                            Some(uplink) => match report.uplinks.get(uplink) {
+                                Some(x) => (x.ipv4, x.ipv6),
+                                None => {
+                                    output::error(t!(
+                                        "uplink-unknown",
+                                        uplink = uplink,
+                                        name = record_name
+                                    ));
+                                    continue;
+                                }
+                            },
+                            None => (x.ipv4, x.ipv6),
+                        },
+                        _ => (ipv4, ipv6),
+                    };
+                    // Not even the fallback of an excluded family is published
+                    let ipv4 = ipv4.filter(|_| family.has_ipv4());
+                    let ipv6 = ipv6.filter(|_| family.has_ipv6());
+                    let mut records = obtain_records(&data_records, record_name).await;
+                    if config.ownership.managed_only {
+                        records.retain(|x| {
+                            let is_owned = config::is_owned(config, x.comment.as_deref());
+                            if !is_owned {
+                                output::info(t!(
+                                    "ownership-skipped",
+                                    record = t!(
+                                        "record-content",
+                                        record_type = x.type_,
+                                        name = x.name,
+                                        zone = zone.name,
+                                        content = x.content
+                                    )
+                                ));
+                            }
+                            is_owned
+                        });
+                    }
+
+                    if let Some(tunnel) = obtain_tunnel(&data_records, record_name).await {
+                        let msg = custom_message(
+                            config.messages.skipped.as_ref(),
+                            t!(
+                                "record-tunnel",
+                                name = record_name,
+                                content = tunnel.content
+                            ),
+                            &[
+                                ("record", record_name),
+                                ("zone", &zone.name),
+                                ("type", "CNAME"),
+                                ("ip", ""),
+                            ],
+                        );
+                        match config.error_on_tunnel {
+                            true => output::error(msg),
+                            false => output::info(msg),
+                        }
+                        report.records.push(Record::tunnel(
+                            &zone.name,
+                            record_name,
+                            &tunnel.content,
+                        ));
+                        continue;
+                    }
+
+                    // The record of a family is created if the name has none of its type, e.g. the
+                    // AAAA record of a name that only has an A record so far
+                    let is_created = options.create_missing || config.create_missing;
+                    let has_type = |type_: &str| {
+                        data_records
+                            .iter()
+                            .any(|x| x.type_.eq_ignore_ascii_case(type_))
+                    };
+                    if is_created && window_open && !has_type("CNAME") {
+                        let missing = (
+                            ipv4.filter(|_| !has_type("A")),
+                            ipv6.filter(|_| !has_type("AAAA")),
+                        );
+                        if missing.0.is_some() || missing.1.is_some() {
+                            create_missing(
+                                &http, &api_base, config, &zone, target, missing, &mut state,
+                                report,
+                            )
+                            .await?;
+                        }
+                        if data_records.is_empty() {
+                            continue;
+                        }
+                    }
+
+                    if records.is_empty() {
+                        output::info(custom_message(
+                            config.messages.skipped.as_ref(),
+                            t!("records-not-found", name = record_name),
+                            &[
+                                ("record", record_name),
+                                ("zone", &zone.name),
+                                ("type", ""),
+                                ("ip", ""),
+                            ],
+                        ));
+                        report
+                            .records
+                            .push(Record::not_found(&zone.name, record_name));
+                        continue;
+                    }
+
+                    if !target.settings.uplinks.is_empty() {
+                        reconcile(
+                            &http,
+                            &api_base,
+                            config,
+                            options,
+                            window_open,
+                            &zone,
+                            target,
+                            records,
+                            &mut state,
+                            report,
+                        )
+                        .await?;
+                        continue;
+                    }
+
+                    let records = match config.dedupe && window_open {
+                        true => {
+                            let desired = [
+                                ipv4.map(IpAddr::V4),
+                                ipv6.map(|x| IpAddr::V6(with_suffix(target, x))),
+                            ];
+                            dedupe(
+                                &http,
+                                &api_base,
+                                config,
+                                &zone,
+                                records,
+                                &desired,
+                                &mut report.records,
+                            )
+                            .await?
+                        }
+                        false => records,
+                    };
+
+                    'outer: for record in records {
+                        let url_dns_record = api_base
+                            .join(format!("zones/{}/dns_records/{}", zone.id, record.id).as_str())
+                            .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 112))?;
+
+                        let timer = Instant::now();
+                        let mut entry = Record {
+                            action: Action::Skipped,
+                            content: record.content.to_owned(),
+                            desired: None,
+                            duration_ms: 0,
+                            external_change: false,
+                            failover,
+                            name: record.name.to_owned(),
+                            proxied: record.proxied,
+                            type_: record.type_.to_owned(),
+                            verified: None,
+                            zone: zone.name.to_owned(),
+                        };
+
+                        let ip: IpAddr = match record.type_.to_uppercase().as_str() {
+                            "A" => 'inner: {
+                                if let Some(ip) = ipv4 {
+                                    break 'inner IpAddr::V4(ip);
+                                }
+                                entry.duration_ms = elapsed_ms(timer);
+                                report.records.push(entry);
+                                continue 'outer;
+                            }
+                            "AAAA" => 'inner: {
+                                if let Some(ip) = ipv6 {
+                                    break 'inner IpAddr::V6(with_suffix(target, ip));
+                                }
+                                entry.duration_ms = elapsed_ms(timer);
+                                report.records.push(entry);
+                                continue 'outer;
+                            }
+                            _ => {
+                                handle_errors(&ErrorKind::NonAddressRecord);
+                                continue;
+                            }
+                        };
+
+                        entry.desired = Some(ip);
+
+                        let msg = t!(
+                            "record-address",
+                            record_type = record.type_,
+                            name = record.name,
+                            zone = zone.name,
+                            address = ip
+                        );
+                        let address = ip.to_string();
+                        let placeholders = [
+                            ("record", record.name.as_str()),
+                            ("zone", zone.name.as_str()),
+                            ("type", record.type_.as_str()),
+                            ("ip", address.as_str()),
+                        ];
+
+                        if let Some(published) = state.records.get(&record.id) {
+                            if published.content != record.content {
+                                entry.external_change = true;
+                                output::error(t!(
+                                    "external-change",
+                                    content = published.content,
+                                    published_at = published.published_at,
+                                    record = msg
+                                ));
+
+                                if config.protect_external_changes && !options.force {
+                                    output::info(custom_message(
+                                        config.messages.skipped.as_ref(),
+                                        t!("external-change-skipped", name = record.name),
+                                        &placeholders,
+                                    ));
+                                    entry.duration_ms = elapsed_ms(timer);
+                                    report.records.push(entry);
+                                    continue;
+                                }
+                            }
+                        }
+
+                        let settings = &target.settings;
+                        // Only the part of the prefix that is kept by the record has to have
+                        // changed
+                        let is_derived = renumbered.is_some_and(|(previous, current)| {
+                            let length = settings.prefix_length.unwrap_or(64);
+                            ip::with_suffix(previous, Ipv6Addr::UNSPECIFIED, length)
+                                != ip::with_suffix(current, Ipv6Addr::UNSPECIFIED, length)
+                        }) && settings.suffix.is_some()
+                            && settings.uplink.is_none()
+                            && !failover
+                            && ip.is_ipv6();
+                        let content_matches =
+                            IpAddr::from_str(&record.content).is_ok_and(|x| x == ip);
+                        let is_owned = config::is_owned(config, record.comment.as_deref());
+                        let (proxied, ttl) =
+                            (config::proxied(config, target), config::ttl(config, target));
+                        let tags = config::tags(config, target);
+                        // Proxy status, tags and TTL that have been changed by hand are reset in
+                        // patch mode as well
+                        let proxied_drift = proxied.filter(|x| *x != record.proxied);
+                        let tags_drift = tags.to_owned().filter(|x| !is_same_tags(x, &record.tags));
+                        let ttl_drift = ttl.filter(|x| *x != record.ttl);
+
+                        // A comment rendered with every update can't be compared with the
+                        // configured one
+                        let replacement = PutDnsRecord {
+                            comment: match &config.messages.comment {
+                                Some(_) => record.comment.to_owned(),
+                                None => config::with_marker(config, settings.comment.to_owned()),
+                            },
+                            content: ip,
+                            name: record.name.to_owned(),
+                            // Fields that are not configured keep their current value, a record
+                            // must not lose its proxy status by switching the mode
+                            proxied: proxied.unwrap_or(record.proxied),
+                            tags: tags.unwrap_or(record.tags.to_owned()),
+                            ttl: ttl.unwrap_or(record.ttl),
+                            type_: record.type_.to_owned(),
+                        };
+
+                        let up_to_date = match settings.mode {
+                            Mode::Patch => {
+                                content_matches
+                                    && is_owned
+                                    && proxied_drift.is_none()
+                                    && tags_drift.is_none()
+                                    && ttl_drift.is_none()
+                            }
+                            Mode::Replace => {
+                                content_matches && is_owned && is_replaced(&record, &replacement)
+                            }
+                        };
+
+                        let published = Published {
+                            content: ip.to_string(),
+                            name: record.name.to_owned(),
+                            published_at: Utc::now(),
+                            type_: record.type_.to_owned(),
+                        };
+
+                        if up_to_date {
+                            let is_recorded = state
+                                .records
+                                .get(&record.id)
+                                .is_some_and(|x| x.content == published.content);
+                            if !is_recorded {
+                                state.records.insert(record.id.to_owned(), published);
+                            }
+                            output::info(custom_message(
+                                config.messages.up_to_date.as_ref(),
+                                t!("up-to-date", record = msg),
+                                &placeholders,
+                            ));
+                            entry.action = Action::UpToDate;
+                            entry.duration_ms = elapsed_ms(timer);
+                            report.records.push(entry);
+                            continue;
+                        }
+
+                        if let (Some(cooldown), Some(published)) =
+                            (settings.cooldown, state.records.get(&record.id))
+                        {
+                            let until = published.published_at
+                                + TimeDelta::seconds(i64::try_from(cooldown).unwrap_or(i64::MAX));
+                            if !options.force && !is_derived && Utc::now() < until {
+                                output::info(t!("cooldown-deferred", until = until, record = msg));
+                                entry.action = Action::Deferred;
+                                entry.duration_ms = elapsed_ms(timer);
+                                report.records.push(entry);
+                                continue;
+                            }
+                        }
+
+                        state
+                            .updates
+                            .retain(|x| Utc::now().signed_duration_since(*x) < TimeDelta::hours(1));
+                        if let Some(limit) = config.max_updates_per_hour {
+                            if !options.force
+                                && !is_derived
+                                && state.updates.len() + pending.len()
+                                    >= usize::try_from(limit).unwrap_or(usize::MAX)
+                            {
+                                output::error(t!("update-limit", limit = limit, record = msg));
+                                entry.duration_ms = elapsed_ms(timer);
+                                report.records.push(entry);
+                                continue;
+                            }
+                        }
+
+                        if !window_open {
+                            output::info(t!("maintenance-queued", record = msg));
+                            entry.action = Action::Queued;
+                            entry.duration_ms = elapsed_ms(timer);
+                            report.records.push(entry);
+                            continue;
+                        }
+
+                        let change = match settings.mode {
+                            Mode::Patch => {
+                                // Records without the marker are claimed by adding it to their
+                                // comment
+                                Change::Patch(PatchDnsRecord {
+                                    comment: match (&config.messages.comment, is_owned) {
+                                        (Some(_), _) => update_comment(config, None, &placeholders),
+                                        (None, true) => None,
+                                        (None, false) => {
+                                            config::with_marker(config, record.comment.to_owned())
+                                        }
+                                    },
+                                    content: Some(ip),
+                                    name: None,
+                                    proxied: proxied_drift,
+                                    tags: tags_drift,
+                                    ttl: ttl_drift,
+                                })
+                            }
+                            Mode::Replace => Change::Put(PutDnsRecord {
+                                comment: update_comment(
+                                    config,
+                                    settings.comment.to_owned(),
+                                    &placeholders,
+                                ),
+                                ..replacement.to_owned()
+                            }),
+                        };
+                        let notice = custom_message(
+                            config.messages.updated.as_ref(),
+                            t!("updated", record = msg),
+                            &placeholders,
+                        );
+
+                        if config.batch {
+                            pending.push(Pending {
+                                change,
+                                entry,
+                                notice,
+                                published,
+                                record,
+                            });
+                            continue;
+                        }
+
+                        let response_record =
+                            send_change(&http, url_dns_record, config, &record, &change)
+                                .await
+                                .map_err(|_| Failure::new(ErrorKind::Api, 113))?;
+
+                        if let Err(e) = deserialize_response(response_record).await {
+                            match e {
+                                ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_) => {
+                                    handle_errors(&e);
+                                    entry.action = Action::Failed;
+                                    entry.duration_ms = elapsed_ms(timer);
+                                    report.records.push(entry);
+                                    continue;
+                                }
+                                _ => return Err(Failure::new(e, 114)),
+                            }
+                        };
+
+                        state.records.insert(record.id.to_owned(), published);
+                        state.updates.push(Utc::now());
+                        output::notice(notice);
+                        entry.proxied = change.proxied(entry.proxied);
+                        entry.action = Action::Updated;
+                        entry.content = ip.to_string();
+                        entry.duration_ms = elapsed_ms(timer);
+                        report.records.push(entry);
+                    }
+                }
+
+                if shutdown::requested() {
+                    break 'zones;
+                }
+
+                if let Some(settings) = config.zones.get(&zone.name) {
+                    if window_open && !shutdown::requested() {
+                        saas::update(&http, &api_base, config, &zone, settings).await?;
+                    }
+                }
+            }
+
+            if window_open && !shutdown::requested() {
+                spectrum::update(&http, &api_base, config, &data_zones, report).await?;
+            }
+
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            failure = Some(e);
+            break;
+        }
+    }
+
+    if config.dns.verify && !shutdown::requested() {
+        verify_records(config, &mut report.records).await;
+    }
+
+    state
+        .cache
+        .zones
+        .retain(|_, x| is_cached(config, x.cached_at));
+
+    // Only a complete check without errors or interruption can be relied on while the addresses are
+    // unchanged, the previous prefix is kept otherwise so that the renumbering is retried
+    let is_interrupted = shutdown::requested();
+    let is_complete =
+        options.records.is_none() && !output::has_errors() && !is_interrupted && failure.is_none();
+    if is_complete || (state.prefix.is_none() && !is_interrupted) {
+        state.prefix = prefix.or(state.prefix);
+    }
+    state.detected = match is_complete {
+        true => Some(Detected {
+            checked_at: Utc::now(),
+            configuration: fingerprint,
+            ipv4: report.ipv4,
+            ipv6: report.ipv6,
+            uplinks: report.uplinks.to_owned(),
+        }),
+        false => None,
+    };
+
+    if let Some(path) = &state_path {
+        if let Err(e) = state::save(path, &state) {
+            handle_errors(&ErrorKind::State(e));
+        }
+    }
+
+    if let Some(e) = failure {
+        return Err(e);
+    }
+
+    actions::run(config, report).await;
+
+    Ok(())
+}
+
+/// New content of a record, either only the changed fields or the whole record
+enum Change {
+    Patch(PatchDnsRecord),
+    Put(PutDnsRecord),
+}
+
+impl Change {
+    /// Proxy status of the record after the change
+    fn proxied(&self, current: bool) -> bool {
+        match self {
+            Change::Patch(x) => x.proxied.unwrap_or(current),
+            Change::Put(x) => x.proxied,
+        }
+    }
+}
+
+/// Change that is sent together with the other changes of the zone
+struct Pending {
+    change: Change,
+    entry: Record,
+    notice: String,
+    published: Published,
+    record: ListDnsRecords,
+}
+
+impl Pending {
+    fn finish(self, is_applied: bool, timer: Instant, state: &mut State, report: &mut Report) {
+        let mut entry = self.entry;
+        entry.duration_ms = elapsed_ms(timer);
+        if !is_applied {
+            entry.action = Action::Failed;
+            report.records.push(entry);
+            return;
+        }
+        output::notice(self.notice);
+        entry.proxied = self.change.proxied(entry.proxied);
+        entry.action = Action::Updated;
+        entry.content = self.published.content.to_owned();
+        state.records.insert(self.record.id, self.published);
+        state.updates.push(Utc::now());
+        report.records.push(entry);
+    }
+}
+
+/// Sends the change of a single record
+async fn send_change(
+    http: &HttpClient,
+    url: Url,
+    config: &Config,
+    record: &ListDnsRecords,
+    change: &Change,
+) -> Result<Response, reqwest::Error> {
+    match change {
+        Change::Patch(x) => patch_or_put(http, url, config, record, x).await,
+        Change::Put(x) => api_put(http, url, Auth::of(config), x).await,
+    }
+}
+
+/// Applies the changes of a zone with a single request, if it fails (none of them is applied then)
+/// they are sent one by one
+async fn update_batch(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    pending: Vec<Pending>,
+    state: &mut State,
+    report: &mut Report,
+) -> Result<(), Failure> {
+    let timer = Instant::now();
+    let url_batch = api_base
+        .join(format!("zones/{}/dns_records/batch", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 166))?;
+
+    let mut payload = BatchDnsRecords {
+        patches: Vec::new(),
+        puts: Vec::new(),
+    };
+    for x in &pending {
+        match &x.change {
+            Change::Patch(record) => payload.patches.push(BatchPatch {
+                id: x.record.id.to_owned(),
+                record: record.to_owned(),
+            }),
+            Change::Put(record) => payload.puts.push(BatchPut {
+                id: x.record.id.to_owned(),
+                record: record.to_owned(),
+            }),
+        }
+    }
+
+    let response = api_post(http, url_batch, Auth::of(config), &payload)
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 167))?;
+    match deserialize_response(response).await {
+        Ok(_) => {
+            for x in pending {
+                x.finish(true, timer, state, report);
+            }
+            return Ok(());
+        }
+        Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+            handle_errors(&e);
+            output::info(t!("batch-fallback", zone = zone.name));
+        }
+        Err(e) => return Err(Failure::new(e, 168)),
+    }
+
+    for x in pending {
+        let timer = Instant::now();
+        let url_dns_record = api_base
+            .join(format!("zones/{}/dns_records/{}", zone.id, x.record.id).as_str())
+            .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 166))?;
+        let response = send_change(http, url_dns_record, config, &x.record, &x.change)
+            .await
+            .map_err(|_| Failure::new(ErrorKind::Api, 167))?;
+
+        let is_applied = match deserialize_response(response).await {
+            Ok(_) => true,
+            Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+                handle_errors(&e);
+                false
+            }
+            Err(e) => return Err(Failure::new(e, 168)),
+        };
+        x.finish(is_applied, timer, state, report);
+    }
+
+    Ok(())
+}
+
+/// Lists all zones of the token, their IDs are cached if enabled
+async fn list_zones(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    account_id: &str,
+    state: &mut State,
+) -> Result<Vec<ListZone>, Failure> {
+    let url_list_zones = api_base
+        .join("zones")
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 104))?;
+
+    let json_zones = api_get_all(http, url_list_zones, Auth::of(config), ZONES_PER_PAGE)
+        .await
+        .map_err(|_| Failure::new(ErrorKind::Api, 105))?
+        .map_err(|e| Failure::new(e, 106))?;
+
+    let listed = deserialize_json_value::<Vec<ListZone>>(json_zones.result)
+        .await
+        .map_err(|e| Failure::new(e, 107))?;
+    if config.cache_ttl > 0 {
+        state.cache.zones.extend(listed.iter().map(|x| {
+            let cached = CachedZone {
+                cached_at: Utc::now(),
+                id: x.id.to_owned(),
+            };
+            (format!("{account_id}/{}", x.name), cached)
+        }));
+    }
+
+    Ok(listed)
+}
+
+/// Whether a cached ID is younger than cache_ttl
+fn is_cached(config: &Config, cached_at: DateTime<Utc>) -> bool {
+    let ttl = TimeDelta::seconds(i64::try_from(config.cache_ttl).unwrap_or(i64::MAX));
+    config.cache_ttl > 0 && Utc::now() - cached_at < ttl
+}
+
+/// Checks that updated records resolve to their new content, retrying to allow for propagation
+async fn verify_records(config: &Config, records: &mut [Record]) {
+    // Proxied records resolve to addresses of Cloudflare instead of their content
+    let mut pending = records
+        .iter_mut()
+        .filter(|x| matches!(x.action, Action::Created | Action::Updated) && !x.proxied)
+        .collect::<Vec<_>>();
+
+    for attempt in 1..=VERIFY_ATTEMPTS {
+        if pending.is_empty() {
+            break;
+        }
+        if attempt > 1 {
+            sleep(VERIFY_DELAY).await;
+        }
+
+        let mut remaining = Vec::new();
+        for record in pending {
+            let (type_, desired) = match (record.type_.to_uppercase().as_str(), record.desired) {
+                ("A", Some(x)) => (RecordType::A, x),
+                ("AAAA", Some(x)) => (RecordType::Aaaa, x),
+                _ => continue,
+            };
+
+            let problem = match dns::lookup(config, &record.name, type_).await {
+                Ok(x) if x.contains(&desired) => {
+                    output::info(t!("verified", name = record.name, address = desired));
+                    record.verified = Some(true);
+                    continue;
+                }
+                Ok(x) if x.is_empty() => t!("verification-no-address"),
+                Ok(x) => x
+                    .iter()
+                    .map(|x| format!("\"{}\"", x))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Err(e) => t!("verification-error", error = e),
+            };
+
+            if attempt == VERIFY_ATTEMPTS {
+                output::error(t!(
+                    "verification-failed",
+                    name = record.name,
+                    result = problem,
+                    address = desired
+                ));
+                record.verified = Some(false);
+            } else {
+                remaining.push(record);
+            }
+        }
+        pending = remaining;
+    }
+}
+
+/// Counts the consecutive failed detections of a record and decides whether its fallback is used
+async fn is_failed_over(
+    config: &Config,
+    state: &mut State,
+    name: &str,
+    failover: &Failover,
+    failed: bool,
+) -> bool {
+    let previous = state.failures.remove(name).unwrap_or_default();
+    let failures = match failed {
+        true => previous.saturating_add(1),
+        false => 0,
+    };
+    if failures > 0 {
+        state.failures.insert(name.to_owned(), failures);
+    }
+
+    let was_failed_over = previous >= failover.after;
+    let is_failed_over = failures >= failover.after;
+
+    match (was_failed_over, is_failed_over) {
+        (false, true) => {
+            output::notice(t!("failover-active", name = name, count = failures));
+            actions::notify_failover(config, name, true).await;
+        }
+        (true, false) => {
+            output::notice(t!("failover-recovered", name = name));
+            actions::notify_failover(config, name, false).await;
+        }
+        _ => {}
+    }
+
+    is_failed_over
+}
+
+/// Applies a message template of the configuration, `{reason}` is replaced with the default message
+fn custom_message(
+    template: Option<&String>,
+    default: String,
+    placeholders: &[(&str, &str)],
+) -> String {
+    let mut message = match template {
+        Some(x) => x.to_owned(),
+        None => return default,
+    };
+    for (key, value) in placeholders {
+        message = message.replace(&format!("{{{key}}}"), value);
+    }
+    message.replace("{reason}", &default)
+}
+
+/// Comment of a created or updated record, rendered from messages.comment if it is configured
+fn update_comment(
+    config: &Config,
+    comment: Option<String>,
+    placeholders: &[(&str, &str)],
+) -> Option<String> {
+    let comment = match &config.messages.comment {
+        Some(template) => {
+            let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+            let hostname = ha::hostname().unwrap_or_default();
+            let mut comment = template
+                .replace("{timestamp}", &timestamp)
+                .replace("{hostname}", &hostname);
+            for (key, value) in placeholders {
+                comment = comment.replace(&format!("{{{key}}}"), value);
+            }
+            Some(comment)
+        }
+        None => comment,
+    };
+    config::with_marker(config, comment)
+}
+
+/// Hash of the configuration, which stays the same as long as the configuration does
+fn fingerprint(config: &Config) -> String {
+    // Converting to a value sorts the keys of the maps
+    let serialized = serde_json::to_value(config)
+        .and_then(|x| serde_json::to_string(&x))
+        .unwrap_or_default();
+    Sha256::digest(serialized.as_bytes())
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+fn elapsed_ms(timer: Instant) -> u64 {
+    u64::try_from(timer.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Deletes all but one record per type, the remaining records are returned
+async fn dedupe(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    records: Vec<ListDnsRecords>,
+    desired: &[Option<IpAddr>],
+    report: &mut Vec<Record>,
+) -> Result<Vec<ListDnsRecords>, Failure> {
+    let mut types = records
+        .iter()
+        .map(|x| x.type_.to_uppercase())
+        .collect::<Vec<_>>();
+    types.sort();
+    types.dedup();
+
+    let mut kept = Vec::new();
+
+    for type_ in types {
+        let mut group = records
+            .iter()
+            .filter(|x| x.type_.to_uppercase() == type_)
+            .cloned()
+            .collect::<Vec<_>>();
+        group.sort_by_key(|x| x.created_on);
+        match config.dedupe_keep {
+            Keep::Oldest => {}
+            Keep::Newest => group.reverse(),
+            // Sorting is stable, so the oldest of the matching records comes first
+            Keep::Matching => group.sort_by_key(|x| {
+                let content = IpAddr::from_str(&x.content).ok();
+                !desired.iter().flatten().any(|x| Some(*x) == content)
+            }),
+        }
+
+        let mut group = group.into_iter();
+        kept.extend(group.next());
+
+        for duplicate in group {
+            let msg = t!(
+                "record-content",
+                record_type = duplicate.type_,
+                name = duplicate.name,
+                zone = zone.name,
+                content = duplicate.content
+            );
+            if !config::is_owned(config, duplicate.comment.as_deref()) {
+                output::info(t!("ownership-refused", record = msg));
+                kept.push(duplicate);
+                continue;
+            }
+
+            let url_dns_record = api_base
+                .join(format!("zones/{}/dns_records/{}", zone.id, duplicate.id).as_str())
+                .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 130))?;
+
+            let response_record = api_delete(http, url_dns_record, Auth::of(config))
+                .await
+                .map_err(|_| Failure::new(ErrorKind::Api, 131))?;
+
+            match deserialize_response(response_record).await {
+                Ok(_) => {
+                    output::notice(t!("duplicate-deleted", record = msg));
+                    report.push(Record {
+                        action: Action::Deleted,
+                        content: duplicate.content.to_owned(),
+                        desired: None,
+                        duration_ms: 0,
+                        external_change: false,
+                        failover: false,
+                        name: duplicate.name.to_owned(),
+                        proxied: duplicate.proxied,
+                        type_: duplicate.type_.to_owned(),
+                        verified: None,
+                        zone: zone.name.to_owned(),
+                    });
+                }
+                Err(e) => match e {
+                    ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_) => {
+                        handle_errors(&e);
+                        kept.push(duplicate);
+                    }
+                    _ => return Err(Failure::new(e, 132)),
+                },
+            }
+        }
+    }
+
+    Ok(kept)
+}
+
+/// Publishes the addresses of several uplinks as records with the same name, so that each type ends
+/// up with one record per address: records of the set are kept, the others are changed to missing
+/// addresses, deleted if there are too many or copied if there are too few
+#[allow(clippy::too_many_arguments)]
+async fn reconcile(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    options: &RunOptions,
+    window_open: bool,
+    zone: &ListZone,
+    target: &Target,
+    records: Vec<ListDnsRecords>,
+    state: &mut State,
+    report: &mut Report,
+) -> Result<(), Failure> {
+    let mut addresses = Vec::new();
+    for uplink in &target.settings.uplinks {
+        match report.uplinks.get(uplink) {
+            Some(x) => {
+                addresses.extend(x.ipv4.map(IpAddr::V4));
+                addresses.extend(x.ipv6.map(IpAddr::V6));
+            }
+            None => output::error(t!("uplink-unknown", uplink = uplink, name = target.name)),
+        }
+    }
+    addresses.sort();
+    addresses.dedup();
+
+    let url_dns_records = api_base
+        .join(format!("zones/{}/dns_records", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 155))?;
+
+    for type_ in ["A", "AAAA"] {
+        let existing = records
+            .iter()
+            .filter(|x| x.type_.eq_ignore_ascii_case(type_))
+            .cloned()
+            .collect::<Vec<_>>();
+        let desired = addresses
+            .iter()
+            .filter(|x| x.is_ipv4() == (type_ == "A"))
+            .copied()
+            .collect::<Vec<_>>();
+        let template = match existing.first() {
+            Some(x) => x.to_owned(),
+            None => continue,
+        };
+
+        let new_entry = |content: &str, desired: Option<IpAddr>| Record {
+            action: Action::Skipped,
+            content: content.to_owned(),
+            desired,
+            duration_ms: 0,
+            external_change: false,
+            failover: false,
+            name: template.name.to_owned(),
+            proxied: template.proxied,
+            type_: template.type_.to_owned(),
+            verified: None,
+            zone: zone.name.to_owned(),
+        };
+
+        // The records are left alone rather than removed while no uplink has an address
+        if desired.is_empty() {
+            for record in &existing {
+                report.records.push(new_entry(&record.content, None));
+            }
+            continue;
+        }
+
+        let (current, mut stale): (Vec<_>, Vec<_>) = existing
+            .into_iter()
+            .partition(|x| IpAddr::from_str(&x.content).is_ok_and(|x| desired.contains(&x)));
+        let mut missing = desired
+            .iter()
+            .filter(|x| !current.iter().any(|y| y.content == x.to_string()))
+            .copied()
+            .collect::<Vec<_>>();
+        // Each address is only kept once, further records with it are reused or deleted
+        let mut seen = Vec::new();
+        for record in current {
+            if seen.contains(&record.content) {
+                stale.push(record);
+                continue;
+            }
+            seen.push(record.content.to_owned());
+
+            output::info(t!(
+                "up-to-date",
+                record = t!(
+                    "record-content",
+                    record_type = record.type_,
+                    name = record.name,
+                    zone = zone.name,
+                    content = record.content
+                )
+            ));
+            let mut entry = new_entry(&record.content, IpAddr::from_str(&record.content).ok());
+            entry.action = Action::UpToDate;
+            report.records.push(entry);
+        }
+
+        let count = stale.len().max(missing.len());
+        let mut stale = stale.into_iter();
+        missing.reverse();
+        for _ in 0..count {
+            let (record, ip) = (stale.next(), missing.pop());
+            let timer = Instant::now();
+            let mut entry = new_entry(record.as_ref().map_or("", |x| x.content.as_str()), ip);
+            let msg = t!(
+                "record-content",
+                record_type = template.type_,
+                name = template.name,
+                zone = zone.name,
+                content = ip.map_or(entry.content.to_owned(), |x| x.to_string())
+            );
+            let address = ip.map(|x| x.to_string()).unwrap_or_default();
+            let placeholders = [
+                ("record", template.name.as_str()),
+                ("zone", zone.name.as_str()),
+                ("type", template.type_.as_str()),
+                ("ip", address.as_str()),
+            ];
+
+            if let (Some(record), None) = (&record, ip) {
+                if !config::is_owned(config, record.comment.as_deref()) {
+                    output::info(t!("ownership-refused", record = msg));
+                    report.records.push(entry);
+                    continue;
+                }
+            }
+            if !window_open {
+                output::info(t!("maintenance-queued", record = msg));
+                entry.action = Action::Queued;
+                report.records.push(entry);
+                continue;
+            }
+            state
+                .updates
+                .retain(|x| Utc::now().signed_duration_since(*x) < TimeDelta::hours(1));
+            if let Some(limit) = config.max_updates_per_hour {
+                if !options.force
+                    && state.updates.len() >= usize::try_from(limit).unwrap_or(usize::MAX)
+                {
+                    output::error(t!("update-limit", limit = limit, record = msg));
+                    report.records.push(entry);
+                    continue;
+                }
+            }
+
+            let settings = &target.settings;
+            let response = match (&record, ip) {
+                (Some(record), Some(ip)) => {
+                    let url = api_base
+                        .join(format!("zones/{}/dns_records/{}", zone.id, record.id).as_str())
+                        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 155))?;
+                    let payload = PatchDnsRecord {
+                        comment: match (
+                            &config.messages.comment,
+                            config::is_owned(config, record.comment.as_deref()),
+                        ) {
+                            (Some(_), _) => update_comment(config, None, &placeholders),
+                            (None, true) => None,
+                            (None, false) => config::with_marker(config, record.comment.to_owned()),
+                        },
+                        content: Some(ip),
+                        name: None,
+                        proxied: config::proxied(config, target).filter(|x| *x != record.proxied),
+                        tags: config::tags(config, target)
+                            .filter(|x| !is_same_tags(x, &record.tags)),
+                        ttl: config::ttl(config, target).filter(|x| *x != record.ttl),
+                    };
+                    patch_or_put(http, url, config, record, &payload).await
+                }
+                (Some(record), None) => {
+                    let url = api_base
+                        .join(format!("zones/{}/dns_records/{}", zone.id, record.id).as_str())
+                        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 155))?;
+                    api_delete(http, url, Auth::of(config)).await
+                }
+                (None, Some(ip)) => {
+                    let payload = PutDnsRecord {
+                        comment: update_comment(
+                            config,
+                            settings.comment.to_owned().or(template.comment.to_owned()),
+                            &placeholders,
+                        ),
+                        content: ip,
+                        name: template.name.to_owned(),
+                        proxied: config::proxied(config, target).unwrap_or(template.proxied),
+                        tags: config::tags(config, target).unwrap_or(template.tags.to_owned()),
+                        ttl: config::ttl(config, target).unwrap_or(template.ttl),
+                        type_: template.type_.to_owned(),
+                    };
+                    api_post(http, url_dns_records.to_owned(), Auth::of(config), &payload).await
+                }
+                (None, None) => continue,
+            }
+            .map_err(|_| Failure::new(ErrorKind::Api, 156))?;
+
+            let result = match deserialize_response(response).await {
+                Ok(x) => x.result,
+                Err(e) => match e {
+                    ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_) => {
+                        handle_errors(&e);
+                        entry.action = Action::Failed;
+                        entry.duration_ms = elapsed_ms(timer);
+                        report.records.push(entry);
+                        continue;
+                    }
+                    _ => return Err(Failure::new(e, 157)),
+                },
+            };
+
+            state.updates.push(Utc::now());
+            let id = match &record {
+                Some(x) => x.id.to_owned(),
+                None => {
+                    deserialize_json_value::<ListDnsRecords>(result)
+                        .await
+                        .map_err(|e| Failure::new(e, 157))?
+                        .id
+                }
+            };
+            match ip {
+                Some(ip) => {
+                    state.records.insert(
+                        id,
+                        Published {
+                            content: ip.to_string(),
+                            name: template.name.to_owned(),
+                            published_at: Utc::now(),
+                            type_: template.type_.to_owned(),
+                        },
+                    );
+                    let (id, action) = match record {
+                        Some(_) => ("updated", Action::Updated),
+                        None => ("member-created", Action::Created),
+                    };
+                    output::notice(t!(id, record = msg));
+                    entry.action = action;
+                    entry.content = ip.to_string();
+                }
+                None => {
+                    state.records.remove(&id);
+                    output::notice(t!("member-deleted", record = msg));
+                    entry.action = Action::Deleted;
+                }
+            }
+            entry.duration_ms = elapsed_ms(timer);
+            report.records.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Address of a record with a suffix, which replaces the interface identifier of the detected one
+fn with_suffix(target: &Target, ip: Ipv6Addr) -> Ipv6Addr {
+    match target.settings.suffix {
+        Some(suffix) => ip::with_suffix(ip, suffix, target.settings.prefix_length.unwrap_or(64)),
+        None => ip,
+    }
+}
+
+/// Creates the A/AAAA records a name has none of yet with the configured settings
+#[allow(clippy::too_many_arguments)]
+async fn create_missing(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    target: &Target,
+    (ipv4, ipv6): (Option<Ipv4Addr>, Option<Ipv6Addr>),
+    state: &mut State,
+    report: &mut Report,
+) -> Result<(), Failure> {
+    let settings = &target.settings;
+    let ipv6 = ipv6.map(|x| with_suffix(target, x));
+    let url_dns_records = api_base
+        .join(format!("zones/{}/dns_records", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 159))?;
+
+    let addresses = [ipv4.map(IpAddr::V4), ipv6.map(IpAddr::V6)];
+    for ip in addresses.into_iter().flatten() {
+        let timer = Instant::now();
+        let type_ = match ip {
+            IpAddr::V4(_) => "A",
+            IpAddr::V6(_) => "AAAA",
+        };
+        let address = ip.to_string();
+        let placeholders = [
+            ("record", target.name.as_str()),
+            ("zone", zone.name.as_str()),
+            ("type", type_),
+            ("ip", address.as_str()),
+        ];
+        let payload = PutDnsRecord {
+            comment: update_comment(config, settings.comment.to_owned(), &placeholders),
+            content: ip,
+            name: target.name.to_owned(),
+            proxied: config::proxied(config, target).unwrap_or(false),
+            tags: config::tags(config, target).unwrap_or_default(),
+            ttl: config::ttl(config, target).unwrap_or(1),
+            type_: String::from(type_),
+        };
+        let mut entry = Record {
+            desired: Some(ip),
+            proxied: payload.proxied,
+            type_: payload.type_.to_owned(),
+            ..Record::not_found(&zone.name, &target.name)
+        };
+        let msg = t!(
+            "record-address",
+            record_type = type_,
+            name = target.name,
+            zone = zone.name,
+            address = ip
+        );
+
+        let response = api_post(http, url_dns_records.to_owned(), Auth::of(config), &payload)
+            .await
+            .map_err(|_| Failure::new(ErrorKind::Api, 160))?;
+        let result = match deserialize_response(response).await {
+            Ok(x) => x.result,
+            Err(e @ (ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_))) => {
+                handle_errors(&e);
+                entry.action = Action::Failed;
+                entry.duration_ms = elapsed_ms(timer);
+                report.records.push(entry);
+                continue;
+            }
+            Err(e) => return Err(Failure::new(e, 161)),
+        };
+        let created = deserialize_json_value::<ListDnsRecords>(result)
+            .await
+            .map_err(|e| Failure::new(e, 161))?;
+
+        state.records.insert(
+            created.id,
+            Published {
+                content: ip.to_string(),
+                name: target.name.to_owned(),
+                published_at: Utc::now(),
+                type_: payload.type_,
+            },
+        );
+        state.updates.push(Utc::now());
+        output::notice(t!("created", record = msg));
+        entry.action = Action::Created;
+        entry.content = ip.to_string();
+        entry.duration_ms = elapsed_ms(timer);
+        report.records.push(entry);
+    }
+
+    Ok(())
+}
+
+/// Changes a record with PATCH, if the method is rejected (e.g. by a proxy) the record is
+/// overwritten with PUT instead, which keeps the current values of the unchanged fields
+async fn patch_or_put(
+    http: &HttpClient,
+    url: Url,
+    config: &Config,
+    record: &ListDnsRecords,
+    payload: &PatchDnsRecord,
+) -> Result<Response, reqwest::Error> {
+    let response = api_patch(http, url.to_owned(), Auth::of(config), payload).await?;
+    // Cloudflare answers invalid changes with 400, only a rejected method is worth another attempt
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+        return Ok(response);
+    }
+    let Some(content) = payload
+        .content
+        .or_else(|| IpAddr::from_str(&record.content).ok())
+    else {
+        return Ok(response);
+    };
+
+    output::info(t!(
+        "patch-rejected",
+        status = response.status().as_u16(),
+        name = record.name
+    ));
+    let replacement = PutDnsRecord {
+        comment: payload.comment.to_owned().or(record.comment.to_owned()),
+        content,
+        name: payload.name.to_owned().unwrap_or(record.name.to_owned()),
+        proxied: payload.proxied.unwrap_or(record.proxied),
+        tags: payload.tags.to_owned().unwrap_or(record.tags.to_owned()),
+        ttl: payload.ttl.unwrap_or(record.ttl),
+        type_: record.type_.to_owned(),
+    };
+    api_put(http, url, Auth::of(config), &replacement).await
+}
+
+/// Whether all fields that are overwritten in replace mode already have the desired value
+fn is_replaced(record: &ListDnsRecords, replacement: &PutDnsRecord) -> bool {
+    record.comment == replacement.comment
+        && record.proxied == replacement.proxied
+        && record.ttl == replacement.ttl
+        && is_same_tags(&record.tags, &replacement.tags)
+}
+
+/// Whether both lists contain the same tags regardless of their order
+fn is_same_tags(a: &[String], b: &[String]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort();
+    b.sort();
+    a == b
+}
+
+pub(crate) async fn obtain_zone(data: &[ListZone], zone_name: &str) -> Option<ListZone> {
+    data.iter().find(|x| x.name == zone_name).cloned()
+}
+
+/// Lists the A and AAAA records of a name (or its CNAME records if there are none), the filtering
+/// is done by the API so that the rest of the zone is neither downloaded nor deserialized
+async fn list_records(
+    http: &HttpClient,
+    api_base: &Url,
+    config: &Config,
+    zone: &ListZone,
+    record_name: &str,
+) -> Result<Result<Vec<ListDnsRecords>, ErrorKind>, Failure> {
+    let url_list_dns_records = api_base
+        .join(format!("zones/{}/dns_records", zone.id).as_str())
+        .map_err(|e| Failure::new(ErrorKind::Unknown(Box::new(e)), 108))?;
+
+    let mut records = Vec::new();
+    for type_ in ["A", "AAAA", "CNAME"] {
+        // Names with addresses cannot have a CNAME record (e.g. of a Cloudflare Tunnel)
+        if type_ == "CNAME" && !records.is_empty() {
+            break;
+        }
+
+        let mut url = url_list_dns_records.to_owned();
+        url.query_pairs_mut()
+            .append_pair("name", record_name)
+            .append_pair("type", type_);
+
+        let response_records = api_get_all(http, url, Auth::of(config), RECORDS_PER_PAGE)
+            .await
+            .map_err(|_| Failure::new(ErrorKind::Api, 109))?;
+
+        let json_records = match response_records {
+            Ok(x) => x,
+            Err(e) => match e {
+                ErrorKind::NoSuccessHttp(_) | ErrorKind::NoSuccessJson(_) => return Ok(Err(e)),
+                _ => return Err(Failure::new(e, 110)),
+            },
+        };
+
+        records.extend(
+            deserialize_json_value::<Vec<ListDnsRecords>>(json_records.result)
+                .await
+                .map_err(|e| Failure::new(e, 111))?,
+        );
+    }
+
+    Ok(Ok(records))
+}
+
+/// CNAME record of a Cloudflare Tunnel route, which takes precedence over address records
+async fn obtain_tunnel(data: &[ListDnsRecords], record_name: &str) -> Option<ListDnsRecords> {
+    data.iter()
+        .filter(|x| x.name == record_name && x.type_.to_uppercase() == "CNAME")
+        .find(|x| {
+            x.content
+                .trim_end_matches('.')
+                .to_lowercase()
+                .ends_with(".cfargotunnel.com")
+        })
+        .cloned()
+}
+
+pub(crate) async fn obtain_records(
+    data: &[ListDnsRecords],
+    record_name: &str,
+) -> Vec<ListDnsRecords> {
+    data.iter()
+        .filter(|x| x.name == record_name)
+        .filter(|x| x.type_.to_uppercase() == "A" || x.type_.to_uppercase() == "AAAA")
+        .cloned()
+        .collect()
+}
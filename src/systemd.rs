@@ -0,0 +1,53 @@
+use std::{
+    env,
+    io::Error as IOError,
+    os::{linux::net::SocketAddrExt, unix::net::SocketAddr, unix::net::UnixDatagram},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Start of the check that is currently running, if any
+pub(crate) type Busy = Arc<Mutex<Option<Instant>>>;
+
+/// Sends a state (e.g. `READY=1`) to the service manager, nothing happens outside of systemd services
+pub(crate) fn notify(state: &str) -> Result<(), IOError> {
+    let path = match env::var_os("NOTIFY_SOCKET") {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+    let path = path.to_string_lossy();
+
+    let address = match path.strip_prefix('@') {
+        Some(x) => SocketAddr::from_abstract_name(x)?,
+        None => SocketAddr::from_pathname(path.as_ref())?,
+    };
+
+    let socket = UnixDatagram::unbound()?;
+    socket.send_to_addr(state.as_bytes(), &address)?;
+    Ok(())
+}
+
+/// Pings the watchdog at half its interval as long as no check takes longer than the interval,
+/// so systemd restarts the service if a check hangs
+pub(crate) fn spawn_watchdog(busy: Busy) {
+    let interval = match env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|x| x.parse::<u64>().ok())
+    {
+        Some(x) if x > 0 => Duration::from_micros(x),
+        _ => return,
+    };
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval / 2).await;
+            let is_hung = busy
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .is_some_and(|x| x.elapsed() > interval);
+            if !is_hung {
+                let _ = notify("WATCHDOG=1");
+            }
+        }
+    });
+}
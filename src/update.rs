@@ -0,0 +1,160 @@
+use crate::{i18n::t, output, structs::github::response::Release, VERSION};
+use reqwest::{header, Client as HttpClient};
+use sha2::{Digest, Sha256};
+use std::{
+    env, fs,
+    io::{Error as IOError, ErrorKind},
+    path::PathBuf,
+};
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/masterflitzer/ddns-cloudflare/releases/latest";
+const TARGET: &str = env!("TARGET");
+
+/// Replaces the running executable with the latest release if it is newer (or `force` is set)
+pub(crate) async fn run(check: bool, force: bool) -> Result<(), IOError> {
+    let http = HttpClient::builder()
+        .user_agent(format!("{}/{}", env!("CARGO_PKG_NAME"), current_version()))
+        .build()
+        .map_err(IOError::other)?;
+
+    let release = http
+        .get(LATEST_RELEASE_URL)
+        .header(header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(|x| x.error_for_status())
+        .map_err(IOError::other)?
+        .json::<Release>()
+        .await
+        .map_err(IOError::other)?;
+
+    let version = release.tag_name.trim_start_matches('v');
+    let current = current_version();
+
+    if !force && !is_newer(version, current) {
+        output::info(t!("self-update-latest", version = current));
+        return Ok(());
+    }
+
+    if check {
+        output::notice(t!(
+            "self-update-available",
+            version = version,
+            current = current
+        ));
+        return Ok(());
+    }
+
+    let name = asset_name();
+    let checksum_name = format!("{name}.sha256");
+    let find = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|x| x.name == name)
+            .map(|x| x.browser_download_url.to_owned())
+    };
+
+    let url = find(&name).ok_or_else(|| {
+        IOError::new(
+            ErrorKind::NotFound,
+            t!("self-update-no-asset", version = version, asset = name),
+        )
+    })?;
+    let checksum_url = find(&checksum_name).ok_or_else(|| {
+        IOError::new(
+            ErrorKind::NotFound,
+            t!(
+                "self-update-no-checksum",
+                version = version,
+                asset = checksum_name
+            ),
+        )
+    })?;
+
+    let download = |url: String| async {
+        http.get(url)
+            .send()
+            .await
+            .and_then(|x| x.error_for_status())
+            .map_err(IOError::other)?
+            .bytes()
+            .await
+            .map_err(IOError::other)
+    };
+
+    let binary = download(url).await?;
+    let checksum = download(checksum_url).await?;
+
+    // The checksum file uses the format of sha256sum ("<hex digest>  <file name>")
+    let expected = String::from_utf8_lossy(&checksum)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let actual = Sha256::digest(&binary)
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect::<String>();
+
+    if expected != actual {
+        return Err(IOError::new(
+            ErrorKind::InvalidData,
+            t!(
+                "self-update-checksum-mismatch",
+                expected = expected,
+                actual = actual
+            ),
+        ));
+    }
+
+    replace_executable(&binary)?;
+    output::notice(t!("self-update-done", version = version, current = current));
+    Ok(())
+}
+
+fn current_version() -> &'static str {
+    VERSION.unwrap_or("0.0.0")
+}
+
+/// Name of the release asset as uploaded by the CI/CD workflow ("<os>-<arch>-<file name>")
+fn asset_name() -> String {
+    let mut parts = TARGET.split('-');
+    let arch = parts.next().unwrap_or_default();
+    let os = parts.nth(1).unwrap_or_default();
+    let file_name = format!("{}{}", env!("CARGO_PKG_NAME"), env::consts::EXE_SUFFIX);
+    format!("{os}-{arch}-{file_name}")
+}
+
+fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |x: &str| {
+        x.split(['.', '-', '+'])
+            .take(3)
+            .map(|x| x.parse::<u64>().unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+    parse(version) > parse(current)
+}
+
+/// Writes the new executable next to the current one and renames it into place
+fn replace_executable(binary: &[u8]) -> Result<(), IOError> {
+    let current = env::current_exe()?.canonicalize()?;
+
+    let mut temporary = PathBuf::from(&current);
+    temporary.as_mut_os_string().push(".new");
+    fs::write(&temporary, binary)?;
+    fs::set_permissions(&temporary, fs::metadata(&current)?.permissions())?;
+
+    // A running executable cannot be replaced on Windows, but it can be renamed
+    if cfg!(windows) {
+        let mut old = PathBuf::from(&current);
+        old.as_mut_os_string().push(".old");
+        let _ = fs::remove_file(&old);
+        fs::rename(&current, &old)?;
+    }
+
+    fs::rename(&temporary, &current).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
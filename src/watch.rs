@@ -0,0 +1,211 @@
+use crate::{
+    errors::handle_errors,
+    i18n::t,
+    output::{self, Console},
+    structs::{
+        config::Config,
+        report::{Action, Record},
+        RunOptions,
+    },
+    sync,
+};
+use chrono::{DateTime, Local};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use std::{
+    io::Error as IOError,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+struct Entry {
+    record: Record,
+    updated_at: Option<DateTime<Local>>,
+}
+
+#[derive(Default)]
+struct State {
+    entries: Vec<Entry>,
+    last_check: Option<DateTime<Local>>,
+    status: String,
+}
+
+impl State {
+    fn update(&mut self, records: Vec<Record>) {
+        let now = Local::now();
+        let entries = records
+            .into_iter()
+            .map(|record| {
+                let updated_at = match record.action {
+                    Action::Created | Action::Updated => Some(now),
+                    _ => self
+                        .entries
+                        .iter()
+                        .find(|x| {
+                            x.record.zone == record.zone
+                                && x.record.name == record.name
+                                && x.record.type_ == record.type_
+                        })
+                        .and_then(|x| x.updated_at),
+                };
+                Entry { record, updated_at }
+            })
+            .collect();
+        self.entries = entries;
+    }
+}
+
+pub(crate) async fn run(
+    config: &Config,
+    options: &RunOptions,
+    interval: Duration,
+) -> Result<(), IOError> {
+    output::set_console(Console::Off);
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, config, options, interval).await;
+    let restored = ratatui::try_restore();
+    output::set_console(Console::Stdout);
+    result.and(restored)
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    config: &Config,
+    options: &RunOptions,
+    interval: Duration,
+) -> Result<(), IOError> {
+    let mut state = State::default();
+    let mut next_check = Instant::now();
+
+    loop {
+        if Instant::now() >= next_check {
+            state.status = t!("watch-checking");
+            terminal.draw(|frame| draw(frame, &state))?;
+
+            match sync::run(config, options).await {
+                Ok(report) => state.update(report.records),
+                Err(e) => handle_errors(&e.kind),
+            }
+
+            state.last_check = Some(Local::now());
+            next_check = Instant::now() + interval;
+        }
+
+        let remaining = next_check.saturating_duration_since(Instant::now());
+        state.status = t!("watch-next-check", seconds = remaining.as_secs());
+        terminal.draw(|frame| draw(frame, &state))?;
+
+        if !event::poll(POLL_INTERVAL)? {
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Char('r') => next_check = Instant::now(),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, state: &State) {
+    let [records_area, log_area, status_area] = Layout::vertical([
+        Constraint::Min(5),
+        Constraint::Length(12),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let header = Row::new([
+        t!("column-zone"),
+        t!("column-name"),
+        t!("column-type"),
+        t!("column-current"),
+        t!("column-desired"),
+        t!("column-status"),
+        t!("column-last-update"),
+    ])
+    .bold();
+
+    let rows = state.entries.iter().map(|entry| {
+        let record = &entry.record;
+        let color = match record.action {
+            Action::Deleted | Action::Failed => Color::Red,
+            Action::Deferred
+            | Action::NotFound
+            | Action::Queued
+            | Action::Skipped
+            | Action::Tunnel => Color::Yellow,
+            Action::UpToDate => Color::Green,
+            Action::Created | Action::Updated => Color::Cyan,
+        };
+        Row::new([
+            record.zone.to_owned(),
+            record.name.to_owned(),
+            record.type_.to_owned(),
+            record.content.to_owned(),
+            record
+                .desired
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| String::from("-")),
+            record.status(),
+            entry
+                .updated_at
+                .map(|x| x.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| String::from("-")),
+        ])
+        .style(Style::new().fg(color))
+    });
+
+    let title = match state.last_check {
+        Some(x) => format!(
+            " {} ",
+            t!("watch-records-checked", time = x.format("%H:%M:%S"))
+        ),
+        None => format!(" {} ", t!("watch-records")),
+    };
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(2),
+            Constraint::Fill(3),
+            Constraint::Length(5),
+            Constraint::Fill(3),
+            Constraint::Fill(3),
+            Constraint::Length(12),
+            Constraint::Length(19),
+        ],
+    )
+    .header(header)
+    .block(Block::bordered().title(title));
+    frame.render_widget(table, records_area);
+
+    let capacity = usize::from(log_area.height.saturating_sub(2));
+    let recent = output::recent();
+    let log = recent
+        .iter()
+        .skip(recent.len().saturating_sub(capacity))
+        .map(|x| Line::from(x.as_str()))
+        .collect::<Vec<_>>();
+    frame.render_widget(
+        Paragraph::new(log).block(Block::bordered().title(format!(" {} ", t!("watch-log")))),
+        log_area,
+    );
+
+    frame.render_widget(Paragraph::new(state.status.as_str()).dim(), status_area);
+}